@@ -22,6 +22,9 @@ pub struct DiagnosticsConfig {
   pub unused_definition: Severity,
   pub repeated_bind: Severity,
   pub recursion_cycle: Severity,
+  pub unconstructed_ctr: Severity,
+  pub self_application: Severity,
+  pub unsigned_underflow: Severity,
 }
 
 #[derive(Debug, Clone)]
@@ -57,6 +60,9 @@ pub enum WarningType {
   UnusedDefinition,
   RepeatedBind,
   RecursionCycle,
+  UnconstructedCtr,
+  SelfApplication,
+  UnsignedUnderflow,
 }
 
 impl Diagnostics {
@@ -231,6 +237,12 @@ impl DiagnosticsConfig {
       unused_definition: severity,
       repeated_bind: severity,
       recursion_cycle: severity,
+      // Opt-in, since values can also come from the arguments of the program.
+      unconstructed_ctr: Severity::Allow,
+      // Opt-in, since it's also how some terminating programs are written.
+      self_application: Severity::Allow,
+      // Opt-in, since wrapping around is sometimes intended, like in `(- 0 1)`.
+      unsigned_underflow: Severity::Allow,
       verbose,
     }
   }
@@ -243,6 +255,9 @@ impl DiagnosticsConfig {
       WarningType::IrrefutableMatch => self.irrefutable_match,
       WarningType::RedundantMatch => self.redundant_match,
       WarningType::UnreachableMatch => self.unreachable_match,
+      WarningType::UnconstructedCtr => self.unconstructed_ctr,
+      WarningType::SelfApplication => self.self_application,
+      WarningType::UnsignedUnderflow => self.unsigned_underflow,
     }
   }
 }
@@ -255,6 +270,16 @@ impl Default for DiagnosticsConfig {
   }
 }
 
+impl Diagnostic {
+  pub fn message(&self) -> &str {
+    &self.message
+  }
+
+  pub fn severity(&self) -> Severity {
+    self.severity
+  }
+}
+
 impl Display for Diagnostic {
   fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
     write!(f, "{}", self.message)
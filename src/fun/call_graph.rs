@@ -0,0 +1,118 @@
+use crate::{
+  fun::{Book, Name},
+  maybe_grow,
+};
+use indexmap::{IndexMap, IndexSet};
+
+impl Book {
+  /// Groups the definitions of the book into the strongly-connected components of its call graph.
+  ///
+  /// Each component is a set of definitions that can all reach each other through their references,
+  /// so a component with more than one definition is a cluster of mutually recursive functions.
+  ///
+  /// The components are returned in topological order of their dependencies:
+  /// a component only comes after the components of every definition it calls.
+  /// Inside a component, the definitions are in the order they appear in the book.
+  pub fn call_graph_sccs(&self) -> Vec<Vec<Name>> {
+    let graph = self
+      .defs
+      .iter()
+      .map(|(nam, def)| {
+        let refs = def.rules.iter().flat_map(|rule| rule.body.collect_refs());
+        let calls = refs.filter_map(|nam| self.defs.get_index_of(&nam)).collect::<IndexSet<_>>();
+        (nam, calls)
+      })
+      .collect::<IndexMap<_, _>>();
+
+    let mut tarjan = Tarjan {
+      graph: &graph,
+      index: vec![None; graph.len()],
+      low_link: vec![0; graph.len()],
+      stack: vec![],
+      on_stack: IndexSet::new(),
+      visited: 0,
+      sccs: vec![],
+    };
+    for def_idx in 0 .. graph.len() {
+      if tarjan.index[def_idx].is_none() {
+        tarjan.visit(def_idx);
+      }
+    }
+
+    tarjan
+      .sccs
+      .into_iter()
+      .map(|mut scc| {
+        scc.sort();
+        scc.into_iter().map(|def_idx| self.defs.get_index(def_idx).unwrap().0.clone()).collect()
+      })
+      .collect()
+  }
+
+  /// The definitions that can reach themselves through their references.
+  ///
+  /// Those are the ones in a component of [`Book::call_graph_sccs`] with more than one definition,
+  /// or alone in their component but referencing themselves.
+  pub fn recursive_defs(&self) -> IndexSet<Name> {
+    self
+      .call_graph_sccs()
+      .into_iter()
+      .filter(|scc| match scc.as_slice() {
+        [nam] => self.defs[nam].rules.iter().any(|rule| rule.body.collect_refs().contains(nam)),
+        _ => true,
+      })
+      .flatten()
+      .collect()
+  }
+}
+
+/// State of Tarjan's algorithm over the call graph, with the definitions identified by their index in the book.
+struct Tarjan<'a> {
+  graph: &'a IndexMap<&'a Name, IndexSet<usize>>,
+  /// The order in which each definition was first visited.
+  index: Vec<Option<usize>>,
+  /// The smallest visit order reachable from each definition that is still in the stack.
+  low_link: Vec<usize>,
+  stack: Vec<usize>,
+  on_stack: IndexSet<usize>,
+  visited: usize,
+  sccs: Vec<Vec<usize>>,
+}
+
+impl Tarjan<'_> {
+  fn visit(&mut self, def_idx: usize) {
+    maybe_grow(|| {
+      self.index[def_idx] = Some(self.visited);
+      self.low_link[def_idx] = self.visited;
+      self.visited += 1;
+      self.stack.push(def_idx);
+      self.on_stack.insert(def_idx);
+
+      for &call in self.graph[def_idx].iter() {
+        match self.index[call] {
+          None => {
+            self.visit(call);
+            self.low_link[def_idx] = self.low_link[def_idx].min(self.low_link[call]);
+          }
+          Some(call_index) if self.on_stack.contains(&call) => {
+            self.low_link[def_idx] = self.low_link[def_idx].min(call_index);
+          }
+          Some(_) => {}
+        }
+      }
+
+      // The root of a component, everything above it in the stack belongs to it.
+      if Some(self.low_link[def_idx]) == self.index[def_idx] {
+        let mut scc = vec![];
+        while let Some(member) = self.stack.pop() {
+          self.on_stack.swap_remove(&member);
+          scc.push(member);
+          if member == def_idx {
+            break;
+          }
+        }
+        self.sccs.push(scc);
+      }
+    })
+  }
+}
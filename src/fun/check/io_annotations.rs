@@ -0,0 +1,32 @@
+use crate::{
+  diagnostics::Diagnostics,
+  fun::{Ctx, Name},
+};
+use indexmap::IndexSet;
+
+impl Ctx<'_> {
+  /// Checks that definitions not annotated with `@io` don't reference any definition annotated with `@io`.
+  ///
+  /// Since every caller of an `@io` definition must also be annotated,
+  /// a pure definition can't reach a side effect through any chain of calls.
+  ///
+  /// Precondition: References have been resolved.
+  pub fn check_io_annotations(&mut self) -> Result<(), Diagnostics> {
+    self.info.start_pass();
+
+    for def in self.book.defs.values() {
+      if def.io {
+        continue;
+      }
+      let refs = def.rules.iter().flat_map(|rule| rule.body.collect_refs()).collect::<IndexSet<Name>>();
+      for nam in refs {
+        if self.book.defs.get(&nam).is_some_and(|def| def.io) {
+          let msg = format!("Uses the '@io' definition '{nam}', but is not annotated with '@io'.");
+          self.info.add_rule_error(msg, def.name.clone());
+        }
+      }
+    }
+
+    self.info.fatal(())
+  }
+}
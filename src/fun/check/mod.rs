@@ -1,3 +1,8 @@
+pub mod io_annotations;
+pub mod self_application;
 pub mod set_entrypoint;
 pub mod shared_names;
 pub mod unbound_vars;
+pub mod unconstructed_ctrs;
+pub mod unsigned_underflow;
+pub mod well_formed;
@@ -0,0 +1,55 @@
+use crate::{
+  diagnostics::{Diagnostics, WarningType},
+  fun::{Ctx, Name, Tag, Term},
+  maybe_grow,
+};
+use indexmap::IndexSet;
+
+impl Ctx<'_> {
+  /// Warns about the variables that are applied to themselves, like in `λx (x x)`.
+  ///
+  /// Self-application is how recursion is built without references, as in the Y combinator,
+  /// so together with other recursive terms it can easily expand forever.
+  /// Only direct applications of a variable to itself are reported.
+  ///
+  /// Precondition: References have been resolved.
+  pub fn check_self_applications(&mut self) -> Result<(), Diagnostics> {
+    self.info.start_pass();
+
+    for def in self.book.defs.values() {
+      let mut self_applied = IndexSet::new();
+      for rule in def.rules.iter() {
+        rule.body.self_applied_vars(&mut self_applied);
+      }
+      for var in self_applied {
+        let msg =
+          format!("Variable '{var}' is applied to itself, which may make the program expand forever.");
+        self.info.add_rule_warning(msg, WarningType::SelfApplication, def.name.clone());
+      }
+    }
+
+    self.info.fatal(())
+  }
+}
+
+impl Term {
+  /// Collects the variables that are the head of an application spine and also one of its arguments.
+  fn self_applied_vars(&self, vars: &mut IndexSet<Name>) {
+    maybe_grow(|| {
+      let mut head = self;
+      let mut args = vec![];
+      while let Term::App { tag: Tag::Static, fun, arg } = head {
+        args.push(arg.as_ref());
+        head = fun;
+      }
+      if let Term::Var { nam } = head
+        && args.iter().any(|arg| matches!(arg, Term::Var { nam: arg } if arg == nam))
+      {
+        vars.insert(nam.clone());
+      }
+      for child in self.children() {
+        child.self_applied_vars(vars);
+      }
+    })
+  }
+}
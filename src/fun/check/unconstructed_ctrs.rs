@@ -0,0 +1,67 @@
+use crate::{
+  diagnostics::{Diagnostics, WarningType},
+  fun::{Ctx, Name, Pattern, Term},
+  hvm::net_refs,
+  maybe_grow,
+};
+use std::collections::HashSet;
+
+impl Ctx<'_> {
+  /// Warns about the constructors of user-defined types that are matched on,
+  /// but that no term of the program ever builds.
+  ///
+  /// The rules and arms matching on these constructors can't be reached,
+  /// unless the values are given by the arguments of the program.
+  /// Constructors of builtin types are never reported, since they can also be built by the runtime.
+  ///
+  /// Precondition: References have been resolved and match definitions not yet desugared.
+  pub fn check_unconstructed_ctrs(&mut self) -> Result<(), Diagnostics> {
+    self.info.start_pass();
+
+    let mut built = HashSet::new();
+    for def in self.book.defs.values() {
+      for rule in def.rules.iter() {
+        // The constructor definitions created by `encode_adts` don't build other constructors.
+        built.extend(rule.body.collect_refs().into_iter().filter(|nam| *nam != def.name));
+      }
+    }
+    built.extend(self.book.hvm_defs.values().flat_map(net_refs).map(Name::new));
+
+    for def in self.book.defs.values() {
+      let mut matched = vec![];
+      for rule in def.rules.iter() {
+        for pat in rule.pats.iter().flat_map(Pattern::iter) {
+          if let Pattern::Ctr(nam, _) = pat {
+            matched.push(nam.clone());
+          }
+        }
+        rule.body.matched_ctrs(&mut matched);
+      }
+
+      let mut reported = HashSet::new();
+      for ctr in matched {
+        let user_defined = self.book.ctrs.get(&ctr).is_some_and(|typ| !self.book.adts[typ].builtin);
+        if user_defined && !built.contains(&ctr) && reported.insert(ctr.clone()) {
+          let msg = format!("Matches on constructor '{ctr}', which is never built by the program.");
+          self.info.add_rule_warning(msg, WarningType::UnconstructedCtr, def.name.clone());
+        }
+      }
+    }
+
+    self.info.fatal(())
+  }
+}
+
+impl Term {
+  /// Collects the names of the arms of the match terms, which may be constructors.
+  fn matched_ctrs(&self, ctrs: &mut Vec<Name>) {
+    maybe_grow(|| {
+      if let Term::Mat { arms, .. } | Term::Fold { arms, .. } = self {
+        ctrs.extend(arms.iter().filter_map(|arm| arm.0.clone()));
+      }
+      for child in self.children() {
+        child.matched_ctrs(ctrs);
+      }
+    })
+  }
+}
@@ -0,0 +1,49 @@
+use crate::{
+  diagnostics::{Diagnostics, WarningType},
+  fun::{Ctx, Num, Op, Term},
+  maybe_grow,
+};
+
+impl Ctx<'_> {
+  /// Warns about subtractions of unsigned numbers that would go below zero, like `(- 3 5)`.
+  ///
+  /// The operation takes the type of its operands, so these wrap around to a large `u24` instead of,
+  /// like `(- +3 +5)` does, giving a negative `i24`.
+  /// Only subtractions of two number literals are checked.
+  pub fn check_unsigned_underflow(&mut self) -> Result<(), Diagnostics> {
+    self.info.start_pass();
+
+    for def in self.book.defs.values() {
+      let mut underflows = vec![];
+      for rule in def.rules.iter() {
+        rule.body.unsigned_underflows(&mut underflows);
+      }
+      for (fst, snd) in underflows {
+        let msg = format!(
+          "Subtraction '(- {fst} {snd})' of unsigned numbers underflows and wraps around to {}. Use signed numbers like '(- +{fst} +{snd})' for a negative result.",
+          (fst as i64 - snd as i64).rem_euclid(1 << 24)
+        );
+        self.info.add_rule_warning(msg, WarningType::UnsignedUnderflow, def.name.clone());
+      }
+    }
+
+    self.info.fatal(())
+  }
+}
+
+impl Term {
+  fn unsigned_underflows(&self, underflows: &mut Vec<(u32, u32)>) {
+    maybe_grow(|| {
+      if let Term::Oper { opr: Op::SUB, fst, snd } = self
+        && let (Term::Num { val: Num::U24(fst) }, Term::Num { val: Num::U24(snd) }) =
+          (fst.as_ref(), snd.as_ref())
+        && fst < snd
+      {
+        underflows.push((*fst, *snd));
+      }
+      for child in self.children() {
+        child.unsigned_underflows(underflows);
+      }
+    })
+  }
+}
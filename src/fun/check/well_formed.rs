@@ -0,0 +1,84 @@
+use crate::fun::{Book, Pattern};
+
+impl Book {
+  /// Panics if any of the internal invariants of the book doesn't hold.
+  ///
+  /// Meant to be called after a transformation to catch bugs early,
+  /// so it only does something when debug assertions are enabled.
+  pub fn assert_well_formed(&self) {
+    if cfg!(debug_assertions)
+      && let Err(err) = self.check_well_formed()
+    {
+      panic!("Book is not well formed:\n{err}");
+    }
+  }
+
+  /// Checks the internal invariants of the book, returning a description of the ones that don't hold:
+  ///
+  /// - Each definition is stored with its own name, and no name is defined both as a function and as a net.
+  /// - Each definition has at least one rule, and all its rules have the same arity.
+  /// - Every reference is to a definition, net or constructor of the book.
+  /// - The constructors of each type are registered as belonging to it, and vice versa.
+  /// - The entrypoint, if set, is a definition of the book.
+  pub fn check_well_formed(&self) -> Result<(), String> {
+    let mut errs = vec![];
+
+    for (nam, def) in self.defs.iter() {
+      if *nam != def.name {
+        errs.push(format!("Definition '{}' is stored under the name '{nam}'.", def.name));
+      }
+      if self.hvm_defs.contains_key(nam) {
+        errs.push(format!("Definition '{nam}' is defined both as a function and as a net."));
+      }
+
+      let Some(arity) = def.rules.first().map(|rule| rule.arity()) else {
+        errs.push(format!("Definition '{nam}' has no rules."));
+        continue;
+      };
+      if def.rules.iter().any(|rule| rule.arity() != arity) {
+        errs.push(format!("Definition '{nam}' has rules with different arities."));
+      }
+
+      for rule in def.rules.iter() {
+        for ref_nam in rule.body.collect_refs() {
+          if !self.defs.contains_key(&ref_nam)
+            && !self.hvm_defs.contains_key(&ref_nam)
+            && !self.ctrs.contains_key(&ref_nam)
+          {
+            errs.push(format!("Definition '{nam}' references '{ref_nam}', which is not defined."));
+          }
+        }
+        for pat in rule.pats.iter().flat_map(Pattern::iter) {
+          if let Pattern::Ctr(ctr, _) = pat
+            && !self.ctrs.contains_key(ctr)
+          {
+            errs.push(format!("Definition '{nam}' matches on '{ctr}', which is not a constructor."));
+          }
+        }
+      }
+    }
+
+    for (adt_nam, adt) in self.adts.iter() {
+      for ctr in adt.ctrs.keys() {
+        if self.ctrs.get(ctr) != Some(adt_nam) {
+          errs.push(format!("Constructor '{ctr}' of type '{adt_nam}' is not registered as belonging to it."));
+        }
+      }
+    }
+    for (ctr, adt_nam) in self.ctrs.iter() {
+      if !self.adts.get(adt_nam).is_some_and(|adt| adt.ctrs.contains_key(ctr)) {
+        errs.push(format!(
+          "Constructor '{ctr}' is registered as belonging to '{adt_nam}', which doesn't have it."
+        ));
+      }
+    }
+
+    if let Some(entrypoint) = &self.entrypoint
+      && !self.defs.contains_key(entrypoint)
+    {
+      errs.push(format!("The entrypoint '{entrypoint}' is not defined."));
+    }
+
+    if errs.is_empty() { Ok(()) } else { Err(errs.join("\n")) }
+  }
+}
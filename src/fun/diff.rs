@@ -0,0 +1,73 @@
+use crate::fun::{Book, Definition, Name};
+use std::{
+  collections::hash_map::DefaultHasher,
+  fmt,
+  hash::{Hash, Hasher},
+};
+
+/// The definitions that differ between two books.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BookDiff {
+  /// Definitions that only exist in the new book.
+  pub added: Vec<Name>,
+  /// Definitions that only exist in the old book.
+  pub removed: Vec<Name>,
+  /// Definitions that exist in both books but with different rules.
+  pub changed: Vec<Name>,
+}
+
+impl Book {
+  /// Compares the definitions of this book against the ones in `other`.
+  ///
+  /// Definitions are matched by name and are considered changed if the hash of their rules differ.
+  /// Useful to check that a pass only touched the definitions it should.
+  pub fn diff(&self, other: &Book) -> BookDiff {
+    let mut diff = BookDiff::default();
+
+    for (name, def) in &self.defs {
+      match other.defs.get(name) {
+        Some(other_def) if def.content_hash() != other_def.content_hash() => diff.changed.push(name.clone()),
+        Some(_) => {}
+        None => diff.removed.push(name.clone()),
+      }
+    }
+
+    for name in other.defs.keys() {
+      if !self.defs.contains_key(name) {
+        diff.added.push(name.clone());
+      }
+    }
+
+    diff
+  }
+}
+
+impl Definition {
+  /// Hashes the rules of the definition, ignoring its name.
+  pub fn content_hash(&self) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    self.rules.hash(&mut hasher);
+    hasher.finish()
+  }
+}
+
+impl BookDiff {
+  pub fn is_empty(&self) -> bool {
+    self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+  }
+}
+
+impl fmt::Display for BookDiff {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for name in &self.added {
+      writeln!(f, "+ {name}")?;
+    }
+    for name in &self.removed {
+      writeln!(f, "- {name}")?;
+    }
+    for name in &self.changed {
+      writeln!(f, "~ {name}")?;
+    }
+    Ok(())
+  }
+}
@@ -143,6 +143,13 @@ impl fmt::Display for Term {
       }
       Term::List { els } => write!(f, "[{}]", DisplayJoin(|| els.iter(), ", "),),
       Term::Open { typ, var, bod } => write!(f, "open {typ} {var}; {bod}"),
+      Term::CtrFields { ctr, fields } => {
+        write!(
+          f,
+          "{ctr} {{ {} }}",
+          DisplayJoin(|| fields.iter().map(|(nam, val)| format!("{nam}: {val}")), ", ")
+        )
+      }
       Term::Err => write!(f, "<Invalid>"),
     })
   }
@@ -190,6 +197,9 @@ impl Rule {
 
 impl fmt::Display for Definition {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if self.io {
+      writeln!(f, "@io")?;
+    }
     write!(f, "{}", DisplayJoin(|| self.rules.iter().map(|x| x.display(&self.name)), "\n"))
   }
 }
@@ -418,6 +428,10 @@ impl Term {
         Term::Open { typ, var, bod } => {
           write!(f, "open {typ} {var};\n{:tab$}{}", "", bod.display_pretty(tab))
         }
+        Term::CtrFields { ctr, fields } => {
+          let fields = fields.iter().map(|(nam, val)| format!("{nam}: {}", val.display_pretty(tab)));
+          write!(f, "{ctr} {{ {} }}", DisplayJoin(|| fields.clone(), ", "))
+        }
         Term::Nat { val } => write!(f, "#{val}"),
         Term::Num { val: Num::U24(val) } => write!(f, "{val}"),
         Term::Num { val: Num::I24(val) } => write!(f, "{}{}", if *val < 0 { "-" } else { "+" }, val.abs()),
@@ -441,3 +455,104 @@ impl Term {
     })
   }
 }
+
+/* ANSI colors */
+
+const KEYWORD_COLOR: &str = "\x1b[35m";
+const OPERATOR_COLOR: &str = "\x1b[33m";
+const NUMBER_COLOR: &str = "\x1b[36m";
+const DEF_NAME_COLOR: &str = "\x1b[1m\x1b[34m";
+const RESET_COLOR: &str = "\x1b[0m";
+
+const KEYWORDS: &[&str] = &[
+  "let", "use", "ask", "do", "with", "switch", "match", "fold", "bend", "when", "else", "open", "if", "def",
+  "return", "type", "object",
+];
+
+/// Adds ANSI colors to displayed terms or books, for printing them to a terminal.
+///
+/// Highlights keywords and `λ`, operators, numbers and the names of the definitions being defined.
+/// String and character literals are left as they are.
+pub fn colorize(code: &str) -> String {
+  fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-' | '/')
+  }
+  fn is_op_char(c: char) -> bool {
+    matches!(c, '+' | '-' | '*' | '/' | '%' | '=' | '!' | '<' | '>' | '&' | '|' | '^')
+  }
+
+  fn paint(out: &mut String, color: &str, tok: &[char]) {
+    out.push_str(color);
+    out.extend(tok);
+    out.push_str(RESET_COLOR);
+  }
+
+  let chars = code.chars().collect::<Vec<_>>();
+  let mut out = String::new();
+  let take_while = |start: usize, pred: &dyn Fn(char) -> bool| {
+    start + chars[start ..].iter().take_while(|c| pred(**c)).count()
+  };
+
+  let mut i = 0;
+  while i < chars.len() {
+    let c = chars[i];
+    let prev = if i == 0 { None } else { Some(chars[i - 1]) };
+    let next = chars.get(i + 1).copied();
+    let start_of_line = i == 0 || prev == Some('\n');
+    match c {
+      // String and character literals, skipping escaped quotes.
+      '"' | '\'' => {
+        let mut end = i + 1;
+        while end < chars.len() && chars[end] != c {
+          end += if chars[end] == '\\' { 2 } else { 1 };
+        }
+        let end = (end + 1).min(chars.len());
+        out.extend(&chars[i .. end]);
+        i = end;
+      }
+      'λ' => {
+        paint(&mut out, KEYWORD_COLOR, &chars[i ..= i]);
+        i += 1;
+      }
+      // Annotations like `@io`.
+      '@' if start_of_line => {
+        let end = take_while(i + 1, &is_name_char);
+        paint(&mut out, KEYWORD_COLOR, &chars[i .. end]);
+        i = end;
+      }
+      // The defined name in `(name args) = body`.
+      '(' if start_of_line && next.is_some_and(is_name_char) => {
+        let end = take_while(i + 1, &is_name_char);
+        out.push('(');
+        paint(&mut out, DEF_NAME_COLOR, &chars[i + 1 .. end]);
+        i = end;
+      }
+      // Operators are always the first thing in a `(op a b)` term.
+      _ if is_op_char(c) && prev == Some('(') && chars.get(take_while(i, &is_op_char)) == Some(&' ') => {
+        let end = take_while(i, &is_op_char);
+        paint(&mut out, OPERATOR_COLOR, &chars[i .. end]);
+        i = end;
+      }
+      _ if c.is_ascii_digit() || (matches!(c, '+' | '-') && next.is_some_and(|c| c.is_ascii_digit())) => {
+        let end = take_while(i + 1, &|c| c.is_ascii_alphanumeric() || c == '.');
+        paint(&mut out, NUMBER_COLOR, &chars[i .. end]);
+        i = end;
+      }
+      _ if c.is_alphabetic() || c == '_' => {
+        let end = take_while(i, &is_name_char);
+        let tok = &chars[i .. end];
+        if KEYWORDS.iter().any(|kw| kw.chars().eq(tok.iter().copied())) {
+          paint(&mut out, KEYWORD_COLOR, tok);
+        } else {
+          out.extend(tok);
+        }
+        i = end;
+      }
+      _ => {
+        out.push(c);
+        i += 1;
+      }
+    }
+  }
+  out
+}
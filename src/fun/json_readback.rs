@@ -0,0 +1,123 @@
+use crate::{
+  fun::{
+    transform::comparisons_to_bool::{FALSE, TRUE},
+    Book, FanKind, Name, Num, Pattern, Tag, Term,
+  },
+  maybe_grow, AdtEncoding,
+};
+use serde_json::{Map, Number, Value};
+
+impl Term {
+  /// Converts a read back term into a JSON value, so that it can be used by other programs.
+  ///
+  /// Numbers, strings, lists and tuples become JSON numbers, strings and arrays.
+  /// If the book declares `type Bool = True | False`, its constructors become JSON booleans,
+  /// and values of types with a single constructor with fields become objects with one entry per field.
+  ///
+  /// A lambda-encoded constructor is only recognized if it can't be confused with
+  /// a constructor of another type defined by the program.
+  /// Any other term becomes a JSON string with the term as it's displayed.
+  /// Floats that are infinite or NaN become `null`, since JSON can't represent them.
+  pub fn to_json(&self, book: &Book, adt_encoding: AdtEncoding) -> Value {
+    maybe_grow(|| match self {
+      Term::Num { val: Num::U24(val) } => Value::from(*val),
+      Term::Num { val: Num::I24(val) } => Value::from(*val),
+      // Goes through the shortest decimal of the f32, so that `0.1` doesn't become `0.10000000149011612`.
+      Term::Num { val: Num::F24(val) } => {
+        val.to_string().parse().ok().and_then(Number::from_f64).map_or(Value::Null, Value::Number)
+      }
+      Term::Str { val } => Value::String(val.to_string()),
+      Term::List { els } => Value::Array(els.iter().map(|el| el.to_json(book, adt_encoding)).collect()),
+      Term::Fan { fan: FanKind::Tup, tag: Tag::Static, els } => {
+        Value::Array(els.iter().map(|el| el.to_json(book, adt_encoding)).collect())
+      }
+      _ => match self.as_ctr(book, adt_encoding) {
+        Some((ctr, fields)) if fields.is_empty() && book.declares_bool() && (ctr == TRUE || ctr == FALSE) => {
+          Value::Bool(ctr == TRUE)
+        }
+        Some((ctr, fields)) if !fields.is_empty() && book.adts[&book.ctrs[ctr]].ctrs.len() == 1 => {
+          let names = book.adts[&book.ctrs[ctr]].ctrs[ctr].iter();
+          let entries =
+            names.zip(fields).map(|(field, val)| (field.nam.to_string(), val.to_json(book, adt_encoding)));
+          Value::Object(entries.collect::<Map<_, _>>())
+        }
+        _ => Value::String(self.to_string()),
+      },
+    })
+  }
+
+  /// If the term is a value built by a constructor of the book, returns it and its fields.
+  fn as_ctr<'a>(&'a self, book: &'a Book, adt_encoding: AdtEncoding) -> Option<(&'a Name, Vec<&'a Term>)> {
+    // A constructor that wasn't expanded, either applied to its fields or by itself.
+    let (head, args) = self.app_spine();
+    if let Term::Ref { nam } = head
+      && let Some(adt) = book.ctrs.get(nam)
+      && book.adts[adt].ctrs[nam].len() == args.len()
+    {
+      return Some((nam, args));
+    }
+
+    let mut found = None;
+    for (ctr, adt) in book.ctrs.iter().filter(|(_, adt)| !book.adts[*adt].builtin) {
+      let adt = &book.adts[adt];
+      let idx = adt.ctrs.get_index_of(ctr).unwrap();
+      let n_fields = adt.ctrs[ctr].len();
+      let fields = match adt_encoding {
+        // `λx (x tag fields...)`
+        AdtEncoding::NumScott => match self {
+          Term::Lam { tag: Tag::Static, pat: box Pattern::Var(Some(var)), bod } => match bod.app_spine() {
+            (Term::Var { nam }, args) if nam == var => match args.split_first() {
+              Some((Term::Num { val: Num::U24(tag) }, fields)) if *tag as usize == idx => {
+                Some((fields.to_vec(), vec![var]))
+              }
+              _ => None,
+            },
+            _ => None,
+          },
+          _ => None,
+        },
+        // `λctr_0 ... λctr_n (ctr_idx fields...)`
+        AdtEncoding::Scott => {
+          let mut binds = vec![];
+          let mut bod = self;
+          while binds.len() < adt.ctrs.len()
+            && let Term::Lam { tag: Tag::Static, pat: box Pattern::Var(bind), bod: next } = bod
+          {
+            binds.push(bind);
+            bod = next;
+          }
+          match (bod.app_spine(), binds.get(idx)) {
+            ((Term::Var { nam }, fields), Some(Some(var))) if binds.len() == adt.ctrs.len() && nam == var => {
+              Some((fields, binds.iter().copied().flatten().collect()))
+            }
+            _ => None,
+          }
+        }
+      };
+      let Some((fields, binds)) = fields else { continue };
+      let captures =
+        fields.iter().any(|field| binds.iter().any(|bind| field.free_vars().contains_key(*bind)));
+      if fields.len() != n_fields || captures {
+        continue;
+      }
+      if found.is_some() {
+        // Could be a value of more than one type.
+        return None;
+      }
+      found = Some((ctr, fields));
+    }
+    found
+  }
+
+  /// The head of a chain of applications and its arguments, in order.
+  fn app_spine(&self) -> (&Term, Vec<&Term>) {
+    let mut args = vec![];
+    let mut head = self;
+    while let Term::App { tag: Tag::Static, fun, arg } = head {
+      args.push(arg.as_ref());
+      head = fun;
+    }
+    args.reverse();
+    (head, args)
+  }
+}
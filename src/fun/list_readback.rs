@@ -0,0 +1,114 @@
+use crate::{
+  diagnostics::Diagnostics,
+  fun::{term_to_net::Labels, Book, Term},
+  readback_hvm_net, AdtEncoding,
+};
+use hvm::ast::{Net, Tree};
+
+/// Reads back a result net that is a list one element at a time,
+/// without building the term of the whole list.
+///
+/// The elements are read back in order as the iterator advances, walking the spine of the list in the net.
+/// The net must be in normal form, and each element must not share variables with the rest of the net,
+/// which holds for lists that don't contain lambdas.
+/// The result net of a run, with the book and labels to read it back, can be got with [`crate::run_book_net`].
+///
+/// If the net stops looking like a list before reaching `List/Nil`,
+/// the iterator yields an error and ends.
+pub struct ListReadback<'a> {
+  /// The rest of the list, or `None` once it has been fully read.
+  tail: Option<&'a Tree>,
+  book: &'a Book,
+  labels: &'a Labels,
+  linear: bool,
+  adt_encoding: AdtEncoding,
+}
+
+impl<'a> ListReadback<'a> {
+  pub fn new(
+    net: &'a Net,
+    book: &'a Book,
+    labels: &'a Labels,
+    linear: bool,
+    adt_encoding: AdtEncoding,
+  ) -> Self {
+    Self { tail: Some(&net.root), book, labels, linear, adt_encoding }
+  }
+}
+
+impl Iterator for ListReadback<'_> {
+  type Item = Result<Term, Diagnostics>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let tail = self.tail.take()?;
+    let cons = match self.adt_encoding {
+      AdtEncoding::NumScott if is_nil_num_scott(tail) => return None,
+      AdtEncoding::NumScott => cons_num_scott(tail),
+      AdtEncoding::Scott if is_nil_scott(tail) => return None,
+      AdtEncoding::Scott => cons_scott(tail),
+    };
+    let Some((head, tail)) = cons else {
+      return Some(Err("Expected the result to be a list.".to_string().into()));
+    };
+    self.tail = Some(tail);
+
+    let net = Net { root: head.clone(), rbag: vec![] };
+    let (term, diags) = readback_hvm_net(&net, self.book, self.labels, self.linear, self.adt_encoding);
+    if diags.has_errors() { Some(Err(diags)) } else { Some(Ok(term)) }
+  }
+}
+
+/// `λx (x 0)`
+fn is_nil_num_scott(tree: &Tree) -> bool {
+  match tree {
+    Tree::Con {
+      fst: box Tree::Con { fst: box Tree::Num { val }, snd: box Tree::Var { nam: ret1 } },
+      snd: box Tree::Var { nam: ret2 },
+    } => is_tag(val, 0) && ret1 == ret2,
+    _ => false,
+  }
+}
+
+/// `λx (x 1 head tail)`
+fn cons_num_scott(tree: &Tree) -> Option<(&Tree, &Tree)> {
+  let Tree::Con {
+    fst:
+      box Tree::Con {
+        fst: box Tree::Num { val },
+        snd: box Tree::Con { fst: head, snd: box Tree::Con { fst: tail, snd: box Tree::Var { nam: ret1 } } },
+      },
+    snd: box Tree::Var { nam: ret2 },
+  } = tree
+  else {
+    return None;
+  };
+  (is_tag(val, 1) && ret1 == ret2).then_some((head, tail))
+}
+
+/// `λ* λx x`
+fn is_nil_scott(tree: &Tree) -> bool {
+  match tree {
+    Tree::Con {
+      fst: box Tree::Era,
+      snd: box Tree::Con { fst: box Tree::Var { nam: x1 }, snd: box Tree::Var { nam: x2 } },
+    } => x1 == x2,
+    _ => false,
+  }
+}
+
+/// `λx λ* (x head tail)`
+fn cons_scott(tree: &Tree) -> Option<(&Tree, &Tree)> {
+  let Tree::Con {
+    fst: box Tree::Con { fst: head, snd: box Tree::Con { fst: tail, snd: box Tree::Var { nam: ret1 } } },
+    snd: box Tree::Con { fst: box Tree::Era, snd: box Tree::Var { nam: ret2 } },
+  } = tree
+  else {
+    return None;
+  };
+  (ret1 == ret2).then_some((head, tail))
+}
+
+fn is_tag(val: &hvm::ast::Numb, tag: u32) -> bool {
+  let numb = hvm::hvm::Numb(val.0);
+  numb.get_typ() == hvm::hvm::TY_U24 && numb.get_u24() == tag
+}
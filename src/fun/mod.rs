@@ -6,18 +6,32 @@ use crate::{
 use indexmap::{IndexMap, IndexSet};
 use interner::global::{GlobalPool, GlobalString};
 use itertools::Itertools;
-use std::{borrow::Cow, collections::HashMap, hash::Hash, ops::Deref};
+use std::{
+  borrow::Cow,
+  collections::{HashMap, HashSet},
+  hash::Hash,
+  ops::Deref,
+};
 
 pub mod builtins;
+pub mod call_graph;
 pub mod check;
+pub mod diff;
 pub mod display;
+pub mod json_readback;
+pub mod list_readback;
 pub mod load_book;
 pub mod net_to_term;
 pub mod parser;
+pub mod precompiled;
+pub mod prelude;
+pub mod signature;
 pub mod term_to_net;
 pub mod transform;
+pub mod whnf_readback;
 
 pub use net_to_term::{net_to_term, ReadbackError};
+use parser::TermParser;
 pub use term_to_net::{book_to_hvm, term_to_hvm};
 
 pub static STRINGS: GlobalPool<String> = GlobalPool::new();
@@ -39,6 +53,9 @@ pub struct Book {
   /// The function definitions.
   pub defs: IndexMap<Name, Definition>,
 
+  /// Already compiled nets that are used as definitions.
+  pub hvm_defs: IndexMap<Name, hvm::ast::Net>,
+
   /// The algebraic datatypes defined by the program
   pub adts: Adts,
 
@@ -58,10 +75,12 @@ pub struct Definition {
   pub name: Name,
   pub rules: Vec<Rule>,
   pub builtin: bool,
+  /// Whether the definition was annotated with `@io`, allowing it to call other `@io` definitions.
+  pub io: bool,
 }
 
 /// A pattern matching rule of a definition.
-#[derive(Debug, Clone, Default, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
 pub struct Rule {
   pub pats: Vec<Pattern>,
   pub body: Term,
@@ -161,6 +180,11 @@ pub enum Term {
     var: Name,
     bod: Box<Term>,
   },
+  /// A constructor applied to named fields, `Foo { b: 2, a: 1 }`.
+  CtrFields {
+    ctr: Name,
+    fields: Vec<(Name, Term)>,
+  },
   Ref {
     nam: Name,
   },
@@ -341,6 +365,7 @@ impl Clone for Term {
       Self::Open { typ, var, bod: nxt } => {
         Self::Open { typ: typ.clone(), var: var.clone(), bod: nxt.clone() }
       }
+      Self::CtrFields { ctr, fields } => Self::CtrFields { ctr: ctr.clone(), fields: fields.clone() },
       Self::Ref { nam } => Self::Ref { nam: nam.clone() },
       Self::Era => Self::Era,
       Self::Err => Self::Err,
@@ -390,6 +415,13 @@ impl From<Option<Name>> for Pattern {
   }
 }
 
+impl From<&str> for Pattern {
+  /// Parses a single pattern, panicking if it's not valid.
+  fn from(code: &str) -> Self {
+    TermParser::new(code).parse_single_pattern().unwrap_or_else(|e| panic!("Invalid pattern '{code}':\n{e}"))
+  }
+}
+
 impl Term {
   /* Common construction patterns */
 
@@ -429,6 +461,26 @@ impl Term {
     args.into_iter().fold(called, |acc, arg| Term::tagged_app(tag.clone(), acc, arg))
   }
 
+  /// An operation with some of its operands missing, which become the arguments of a lambda.
+  /// ```bend
+  /// (+)   => λ%x λ%y (+ %x %y)
+  /// (+ 1) => λ%x (+ %x 1)
+  /// (1 +) => λ%x (+ 1 %x)
+  /// ```
+  pub fn oper_section(opr: Op, fst: Option<Term>, snd: Option<Term>) -> Self {
+    let mut args = vec![];
+    let mut operand = |term: Option<Term>| {
+      term.unwrap_or_else(|| {
+        let nam = Name::new(if args.is_empty() { "%x" } else { "%y" });
+        args.push(Some(nam.clone()));
+        Term::Var { nam }
+      })
+    };
+    let fst = operand(fst);
+    let snd = operand(snd);
+    Self::rfold_lams(Term::Oper { opr, fst: Box::new(fst), snd: Box::new(snd) }, args.into_iter())
+  }
+
   /// Apply a variable to a term by the var name.
   pub fn arg_call(fun: Term, arg: Name) -> Self {
     Term::app(fun, Term::Var { nam: arg })
@@ -474,7 +526,7 @@ impl Term {
 
   /* Iterators */
   pub fn children(&self) -> impl DoubleEndedIterator<Item = &Term> + Clone {
-    multi_iterator!(ChildrenIter { Zero, One, Two, Vec, Mat, Swt, Bend, Fold });
+    multi_iterator!(ChildrenIter { Zero, One, Two, Vec, Mat, Swt, Bend, Fold, Fields });
     match self {
       Term::Mat { arg, bnd: _, with: _, arms } => {
         ChildrenIter::Mat([arg.as_ref()].into_iter().chain(arms.iter().map(|r| &r.2)))
@@ -489,6 +541,7 @@ impl Term {
         ChildrenIter::Fold([arg.as_ref()].into_iter().chain(arms.iter().map(|r| &r.2)))
       }
       Term::Fan { els, .. } | Term::List { els } => ChildrenIter::Vec(els),
+      Term::CtrFields { ctr: _, fields } => ChildrenIter::Fields(fields.iter().map(|(_, val)| val)),
       Term::Let { val: fst, nxt: snd, .. }
       | Term::Ask { val: fst, nxt: snd, .. }
       | Term::Use { val: fst, nxt: snd, .. }
@@ -509,7 +562,7 @@ impl Term {
   }
 
   pub fn children_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut Term> {
-    multi_iterator!(ChildrenIter { Zero, One, Two, Vec, Mat, Swt, Bend, Fold });
+    multi_iterator!(ChildrenIter { Zero, One, Two, Vec, Mat, Swt, Bend, Fold, Fields });
     match self {
       Term::Mat { arg, bnd: _, with: _, arms } => {
         ChildrenIter::Mat([arg.as_mut()].into_iter().chain(arms.iter_mut().map(|r| &mut r.2)))
@@ -524,6 +577,7 @@ impl Term {
         ChildrenIter::Fold([arg.as_mut()].into_iter().chain(arms.iter_mut().map(|r| &mut r.2)))
       }
       Term::Fan { els, .. } | Term::List { els } => ChildrenIter::Vec(els),
+      Term::CtrFields { ctr: _, fields } => ChildrenIter::Fields(fields.iter_mut().map(|(_, val)| val)),
       Term::Let { val: fst, nxt: snd, .. }
       | Term::Ask { val: fst, nxt: snd, .. }
       | Term::Use { val: fst, nxt: snd, .. }
@@ -555,7 +609,7 @@ impl Term {
     &self,
   ) -> impl DoubleEndedIterator<Item = (&Term, impl DoubleEndedIterator<Item = &Option<Name>> + Clone)> + Clone
   {
-    multi_iterator!(ChildrenIter { Zero, One, Two, Vec, Mat, Swt, Bend, Fold });
+    multi_iterator!(ChildrenIter { Zero, One, Two, Vec, Mat, Swt, Bend, Fold, Fields });
     multi_iterator!(BindsIter { Zero, One, Mat, Pat, Bend });
     match self {
       Term::Mat { arg, bnd: _, with: _, arms } => ChildrenIter::Mat(
@@ -587,6 +641,9 @@ impl Term {
       Term::Fan { els, .. } | Term::List { els } => {
         ChildrenIter::Vec(els.iter().map(|el| (el, BindsIter::Zero([]))))
       }
+      Term::CtrFields { ctr: _, fields } => {
+        ChildrenIter::Fields(fields.iter().map(|(_, val)| (val, BindsIter::Zero([]))))
+      }
       Term::Let { pat, val, nxt, .. } | Term::Ask { pat, val, nxt, .. } => {
         ChildrenIter::Two([(val.as_ref(), BindsIter::Zero([])), (nxt.as_ref(), BindsIter::Pat(pat.binds()))])
       }
@@ -615,7 +672,7 @@ impl Term {
     &mut self,
   ) -> impl DoubleEndedIterator<Item = (&mut Term, impl DoubleEndedIterator<Item = &Option<Name>> + Clone)>
   {
-    multi_iterator!(ChildrenIter { Zero, One, Two, Vec, Mat, Swt, Bend, Fold });
+    multi_iterator!(ChildrenIter { Zero, One, Two, Vec, Mat, Swt, Bend, Fold, Fields });
     multi_iterator!(BindsIter { Zero, One, Mat, Pat, Bend });
     match self {
       Term::Mat { arg, bnd: _, with: _, arms: rules } => ChildrenIter::Mat(
@@ -647,6 +704,9 @@ impl Term {
       Term::Fan { els, .. } | Term::List { els } => {
         ChildrenIter::Vec(els.iter_mut().map(|el| (el, BindsIter::Zero([]))))
       }
+      Term::CtrFields { ctr: _, fields } => {
+        ChildrenIter::Fields(fields.iter_mut().map(|(_, val)| (val, BindsIter::Zero([]))))
+      }
       Term::Let { pat, val, nxt, .. } | Term::Ask { pat, val, nxt, .. } => {
         ChildrenIter::Two([(val.as_mut(), BindsIter::Zero([])), (nxt.as_mut(), BindsIter::Pat(pat.binds()))])
       }
@@ -674,7 +734,7 @@ impl Term {
   pub fn children_mut_with_binds_mut(
     &mut self,
   ) -> impl DoubleEndedIterator<Item = (&mut Term, impl DoubleEndedIterator<Item = &mut Option<Name>>)> {
-    multi_iterator!(ChildrenIter { Zero, One, Two, Vec, Mat, Swt, Fold });
+    multi_iterator!(ChildrenIter { Zero, One, Two, Vec, Mat, Swt, Fold, Fields });
     multi_iterator!(BindsIter { Zero, One, Mat, Pat });
     match self {
       Term::Mat { arg, bnd: _, with: _, arms: rules } => ChildrenIter::Mat(
@@ -702,6 +762,9 @@ impl Term {
       Term::Fan { els, .. } | Term::List { els } => {
         ChildrenIter::Vec(els.iter_mut().map(|el| (el, BindsIter::Zero([]))))
       }
+      Term::CtrFields { ctr: _, fields } => {
+        ChildrenIter::Fields(fields.iter_mut().map(|(_, val)| (val, BindsIter::Zero([]))))
+      }
       Term::Use { nam, val, nxt } => {
         ChildrenIter::Two([(val.as_mut(), BindsIter::Zero([])), (nxt.as_mut(), BindsIter::One([nam]))])
       }
@@ -798,6 +861,33 @@ impl Term {
     free_vars
   }
 
+  /// Returns every name bound anywhere in the term, by lambdas, lets, dups, matches
+  /// and the other binding terms, including the unscoped variables bound by channels.
+  ///
+  /// Unlike `free_vars`, this doesn't look at scopes, so it's a quick way
+  /// of checking whether a name could conflict with the variables of the term.
+  pub fn all_binders(&self) -> HashSet<Name> {
+    fn go_term(term: &Term, binders: &mut HashSet<Name>) {
+      maybe_grow(|| {
+        if let Term::Lam { pat, .. } | Term::Let { pat, .. } = term {
+          for pat in pat.iter() {
+            if let Pattern::Chn(nam) = pat {
+              binders.insert(nam.clone());
+            }
+          }
+        }
+        for (child, binds) in term.children_with_binds() {
+          binders.extend(binds.flatten().cloned());
+          go_term(child, binders);
+        }
+      })
+    }
+
+    let mut binders = HashSet::new();
+    go_term(self, &mut binders);
+    binders
+  }
+
   /// Returns the set of declared and the set of used unscoped variables
   pub fn unscoped_vars(&self) -> (IndexSet<Name>, IndexSet<Name>) {
     fn go_pat(pat: &Pattern, decls: &mut IndexSet<Name>) {
@@ -832,6 +922,51 @@ impl Term {
     (decls, uses)
   }
 
+  /// Collects the names of all the definitions referenced by a term.
+  pub fn collect_refs(&self) -> IndexSet<Name> {
+    fn go_term(term: &Term, refs: &mut IndexSet<Name>) {
+      maybe_grow(|| {
+        if let Term::Ref { nam } = term {
+          refs.insert(nam.clone());
+        }
+        for child in term.children() {
+          go_term(child, refs);
+        }
+      })
+    }
+    let mut refs = IndexSet::new();
+    go_term(self, &mut refs);
+    refs
+  }
+
+  /// Replaces each reference for which `get_body` returns a term with that term,
+  /// without looking inside the inlined terms.
+  ///
+  /// Used by the passes that inline definitions, with `get_body` deciding which references to replace.
+  pub fn inline_refs(&mut self, get_body: &mut impl FnMut(&Name) -> Option<Term>) {
+    maybe_grow(|| match self {
+      Term::Ref { nam } => {
+        if let Some(body) = get_body(nam) {
+          *self = body;
+        }
+      }
+      _ => {
+        for child in self.children_mut() {
+          child.inline_refs(get_body);
+        }
+      }
+    })
+  }
+
+  /// Whether the term is a number or an operation on numbers.
+  pub fn is_constant(&self) -> bool {
+    maybe_grow(|| match self {
+      Term::Num { .. } => true,
+      Term::Oper { fst, snd, .. } => fst.is_constant() && snd.is_constant(),
+      _ => false,
+    })
+  }
+
   pub fn has_unscoped(&self) -> bool {
     maybe_grow(|| {
       let mut has_unscoped = match self {
@@ -892,6 +1027,30 @@ impl PartialEq for Num {
 impl Eq for Num {}
 
 impl Pattern {
+  /* Common construction patterns */
+
+  pub fn ctr(name: &str, args: impl IntoIterator<Item = Pattern>) -> Self {
+    Pattern::Ctr(Name::new(name), args.into_iter().collect())
+  }
+
+  pub fn var(name: &str) -> Self {
+    Pattern::Var(Some(Name::new(name)))
+  }
+
+  /// A variable pattern that doesn't bind anything, `*`.
+  pub fn wildcard() -> Self {
+    Pattern::Var(None)
+  }
+
+  pub fn num(val: u32) -> Self {
+    Pattern::Num(val)
+  }
+
+  /// A tuple pattern with a static tag.
+  pub fn tup(els: impl IntoIterator<Item = Pattern>) -> Self {
+    Pattern::Fan(FanKind::Tup, Tag::Static, els.into_iter().collect())
+  }
+
   pub fn binds(&self) -> impl DoubleEndedIterator<Item = &Option<Name>> + Clone {
     self.iter().filter_map(|pat| match pat {
       Pattern::Var(nam) => Some(nam),
@@ -974,6 +1133,31 @@ impl Rule {
   pub fn arity(&self) -> usize {
     self.pats.len()
   }
+
+  /// Whether the rule just returns its only argument or a constant.
+  ///
+  /// The identity can be either `(f x) = x` or, without patterns, `(f) = λx x`.
+  /// The argument can also be renamed with `use`, like in `(f) = λa use x = a; x`,
+  /// which is how the identity looks once the pattern matching rules are desugared.
+  /// A constant is a number or an operation on constants, like `(f *) = 1` or `(f) = (+ 2 3)`.
+  pub fn is_trivial(&self) -> bool {
+    match (self.pats.as_slice(), &self.body) {
+      ([Pattern::Var(Some(arg))], Term::Var { nam }) => arg == nam,
+      ([], Term::Lam { pat: box Pattern::Var(Some(arg)), bod, .. }) => {
+        let mut arg = arg;
+        let mut bod = bod.as_ref();
+        while let Term::Use { nam: Some(nam), val: box Term::Var { nam: val }, nxt } = bod {
+          if val != arg {
+            return false;
+          }
+          arg = nam;
+          bod = nxt;
+        }
+        matches!(bod, Term::Var { nam } if nam == arg)
+      }
+      (_, body) => body.is_constant(),
+    }
+  }
 }
 
 impl Definition {
@@ -1054,6 +1238,95 @@ impl Book {
       Some(nam) => nam,
     }
   }
+
+  /// Returns the definitions that have a reference to `target` in any of their rules.
+  ///
+  /// Since it looks for `Ref` terms, it only finds the uses after references are resolved.
+  pub fn defs_referencing(&self, target: &Name) -> Vec<Name> {
+    self
+      .defs
+      .values()
+      .filter(|def| def.rules.iter().any(|rule| rule.body.collect_refs().contains(target)))
+      .map(|def| def.name.clone())
+      .collect()
+  }
+
+  /// Returns the names of the constructors that appear in the patterns of any rule.
+  ///
+  /// Nullary constructors are only found after `fix_match_defs`, which tells them apart from variables.
+  pub fn pattern_ctrs(&self) -> IndexSet<Name> {
+    let pats = self.defs.values().flat_map(|def| def.rules.iter()).flat_map(|rule| rule.pats.iter());
+    pats
+      .flat_map(Pattern::iter)
+      .filter_map(|pat| match pat {
+        Pattern::Ctr(nam, _) => Some(nam.clone()),
+        _ => None,
+      })
+      .collect()
+  }
+
+  /// Returns the constructors that appear in patterns but aren't declared by any type.
+  pub fn undeclared_pattern_ctrs(&self) -> Vec<Name> {
+    self.pattern_ctrs().into_iter().filter(|nam| !self.ctrs.contains_key(nam)).collect()
+  }
+
+  /// Returns the name, id and arity of every definition, sorted by name.
+  ///
+  /// The id of a definition is its position in the book, which only changes when definitions are added or removed.
+  /// The arity is the number of patterns of its rules or, once match definitions were desugared,
+  /// the number of lambdas around its body.
+  pub fn symbol_table(&self) -> Vec<(Name, usize, usize)> {
+    self
+      .defs
+      .values()
+      .enumerate()
+      .map(|(id, def)| {
+        let arity = match def.rules.as_slice() {
+          [rule] if rule.pats.is_empty() => {
+            let mut body = &rule.body;
+            let mut arity = 0;
+            while let Term::Lam { bod, .. } = body {
+              arity += 1;
+              body = bod;
+            }
+            arity
+          }
+          _ => def.arity(),
+        };
+        (def.name.clone(), id, arity)
+      })
+      .sorted_by(|(a, ..), (b, ..)| a.cmp(b))
+      .collect()
+  }
+
+  /// Returns the sorted and deduplicated values of all the `u24` and `i24` literals in the book.
+  pub fn numeric_literals(&self) -> (Vec<u32>, Vec<i32>) {
+    fn go_term(term: &Term, u24s: &mut Vec<u32>, i24s: &mut Vec<i32>) {
+      maybe_grow(|| {
+        match term {
+          Term::Num { val: Num::U24(val) } => u24s.push(*val),
+          Term::Num { val: Num::I24(val) } => i24s.push(*val),
+          _ => {}
+        }
+        for child in term.children() {
+          go_term(child, u24s, i24s);
+        }
+      })
+    }
+
+    let mut u24s = vec![];
+    let mut i24s = vec![];
+    for def in self.defs.values() {
+      for rule in def.rules.iter() {
+        go_term(&rule.body, &mut u24s, &mut i24s);
+      }
+    }
+    u24s.sort_unstable();
+    u24s.dedup();
+    i24s.sort_unstable();
+    i24s.dedup();
+    (u24s, i24s)
+  }
 }
 
 #[test]
@@ -10,16 +10,17 @@ use highlight_error::highlight_error;
 use TSPL::Parser;
 
 // Bend grammar description:
-// <Book>       ::= (<Data> | <Rule>)*
+// <Book>       ::= (<Data> | "@io"? ("@priority" <Number>)? <Rule>)*
 // <ADT>        ::= "type" <Name> "=" ( <Name> | "(" <Name> (<Name>)* ")" )+
-// <Rule>       ::= ("(" <Name> <Pattern>* ")" | <Name> <Pattern>*) "=" <Term>
+// <Rule>       ::= ("(" <Name> <Pattern>* ")" | <Name> <Pattern>* | "|" <Pattern>*) "=" <Term>
 // <Pattern>    ::= "(" <Name> <Pattern>* ")" | <NameEra> | <Number> | "(" <Pattern> ("," <Pattern>)+ ")"
 // <Term>       ::=
-//   <Number> | <NumOp> | <Tup> | <App> | <Group> | <Nat> | <Lam> | <UnscopedLam> | <Bend> | <Fold> |
-//   <Use> | <Dup> | <LetTup> | <Let> | <Bind> | <Match> | <Switch> | <Era> | <UnscopedVar> | <Var>
-// <Lam>        ::= <Tag>? ("λ"|"@") <NameEra> <Term>
-// <UnscopedLam>::= <Tag>? ("λ"|"@") "$" <Name> <Term>
+//   <Number> | <NumOp> | <OprSection> | <Tup> | <App> | <Group> | <Nat> | <Lam> | <UnscopedLam> | <Bend> | <Fold> |
+//   <Use> | <Dup> | <LetTup> | <Let> | <Bind> | <Match> | <Switch> | <Era> | <UnscopedVar> | <CtrFields> | <Var>
+// <Lam>        ::= <Tag>? ("λ"|"@"|"\") <NameEra> <Term>
+// <UnscopedLam>::= <Tag>? ("λ"|"@"|"\") "$" <Name> <Term>
 // <NumOp>      ::= "(" <Operator> <Term> <Term> ")"
+// <OprSection> ::= "(" <Operator> <Term>? ")" | "(" <Term> <Operator> ")"
 // <Tup>        ::= "(" <Term> ("," <Term>)+ ")"
 // <App>        ::= <Tag>? "(" <Term> (<Term>)+ ")"
 // <Group>      ::= "(" <Term> ")"
@@ -32,10 +33,12 @@ use TSPL::Parser;
 // <List>       ::= "[" (<Term> ","?)* "]"
 // <String>     ::= "\"" (escape sequence | [^"])* "\""
 // <Char>       ::= "'" (escape sequence | [^']) "'"
-// <Match>      ::= "match" <Name> ("=" <Term>)? ("with" <Var> (","? <Var>)*)? "{" <MatchArm>+ "}"
-// <MatchArm>   ::= "|"? <Pattern> ":" <Term> ";"?
-// <Switch>     ::= "switch" <Name> ("=" <Term>)? ("with" <Var> (","? <Var>)*)? "{" <SwitchArm>+ "}"
-// <SwitchArm>  ::= "|"? (<Num>|"_") ":" <Term> ";"?
+// <Match>      ::= "match" <Name> ("=" <Term>)? ("with" <Var> (","? <Var>)*)? <MatchArms>
+// <MatchArms>  ::= "{" <MatchArm>+ "}" | ":" <MatchArm>+ (indented further than the header)
+// <MatchArm>   ::= "|"? (<NameEra>|"otherwise") ":" <Term> ";"?
+// <Switch>     ::= "switch" <Name> ("=" <Term>)? ("with" <Var> (","? <Var>)*)? ("{" <SwitchArm>+ "}" | ":" <SwitchArm>+)
+// <SwitchArm>  ::= "|"? (<Num>|"_"|"otherwise") ":" <Term> ";"?
+// <CtrFields>  ::= <Name> "{" (<Name> ":" <Term> ("," <Name> ":" <Term>)*)? ","? "}"
 // <Var>        ::= <Name>
 // <UnscopedVar>::= "$" <Name>
 // <NameEra>    ::= <Name> | "*"
@@ -62,12 +65,41 @@ impl<'a> TermParser<'a> {
   pub fn parse_book(&mut self, default_book: Book, builtin: bool) -> ParseResult<Book> {
     let mut book = default_book;
     let mut indent = self.advance_newlines();
-    let mut last_rule = None;
+    let mut last_rule: Option<Name> = None;
+    // The priorities of the rules of the last definition, in the order they are stored.
+    let mut last_priorities = vec![];
     while !self.is_eof() {
       let ini_idx = *self.index();
 
+      // Effect annotation of the next definition
+      let io = self.try_parse_keyword("@io");
+      if io {
+        indent = self.advance_newlines();
+      }
+
+      // Matching priority of the next rule
+      let priority = if self.try_parse_keyword("@priority") {
+        self.skip_trivia_inline();
+        let priority = self.parse_u32()?;
+        indent = self.advance_newlines();
+        Some(priority)
+      } else {
+        None
+      };
+      let only_rules = |p: &mut Self| {
+        let end_idx = *p.index();
+        p.with_ctx(Err("Only rules can be annotated with '@priority'."), ini_idx, end_idx)
+      };
+
       // Record type definition
       if self.try_parse_keyword("object") {
+        if priority.is_some() {
+          return only_rules(self);
+        }
+        if io {
+          let end_idx = *self.index();
+          return self.with_ctx(Err("Only functions can be annotated with '@io'."), ini_idx, end_idx);
+        }
         let mut prs = PyParser { input: self.input, index: *self.index() };
         let (obj, nxt_indent) = prs.parse_object(indent)?;
         self.index = prs.index;
@@ -80,11 +112,16 @@ impl<'a> TermParser<'a> {
 
       // Imp function definition
       if self.try_parse_keyword("def") {
+        if priority.is_some() {
+          return only_rules(self);
+        }
         let mut prs = PyParser { input: self.input, index: *self.index() };
         let (def, nxt_indent) = prs.parse_def(indent)?;
         self.index = prs.index;
         let end_idx = *self.index();
+        let def_name = def.name.clone();
         prs.add_def(def, &mut book, ini_idx, end_idx, builtin)?;
+        book.defs[&def_name].io = io;
         indent = nxt_indent;
         last_rule = None;
         continue;
@@ -92,6 +129,13 @@ impl<'a> TermParser<'a> {
 
       // Fun/Imp type definition
       if self.try_parse_keyword("type") {
+        if priority.is_some() {
+          return only_rules(self);
+        }
+        if io {
+          let end_idx = *self.index();
+          return self.with_ctx(Err("Only functions can be annotated with '@io'."), ini_idx, end_idx);
+        }
         self.skip_trivia();
         let rewind_index = self.index;
 
@@ -119,14 +163,23 @@ impl<'a> TermParser<'a> {
 
       // Fun function definition
       let ini_idx = *self.index();
-      let (name, rule) = self.parse_rule()?;
+      let (name, rule) = match &last_rule {
+        // A rule continuing the previous definition, without repeating its name.
+        Some(name) if self.try_consume_exactly("|") => (name.clone(), self.parse_rule_continuation()?),
+        _ => self.parse_rule()?,
+      };
       let end_idx = *self.index();
       // Add to book
       if let Some(def) = book.defs.get_mut(&name) {
         if let Some(last_rule) = last_rule {
           if last_rule == name {
-            // Continuing with a new rule to the current definition
-            def.rules.push(rule);
+            // Continuing with a new rule to the current definition.
+            // Rules with a higher priority are matched first, otherwise they're kept in source order.
+            let priority = priority.unwrap_or(0);
+            let idx = last_priorities.iter().take_while(|&&prev| prev >= priority).count();
+            def.rules.insert(idx, rule);
+            last_priorities.insert(idx, priority);
+            def.io |= io;
           } else {
             // Trying to add a new rule to a previous definition, coming from a different rule.
             let msg = format!("Redefinition of function '{name}'");
@@ -139,7 +192,8 @@ impl<'a> TermParser<'a> {
         }
       } else {
         // Adding the first rule of a new definition
-        book.defs.insert(name.clone(), Definition { name: name.clone(), rules: vec![rule], builtin });
+        book.defs.insert(name.clone(), Definition { name: name.clone(), rules: vec![rule], builtin, io });
+        last_priorities = vec![priority.unwrap_or(0)];
       }
       indent = self.advance_newlines();
       last_rule = Some(name);
@@ -212,6 +266,29 @@ impl<'a> TermParser<'a> {
     Ok((name, rule))
   }
 
+  /// Parses the patterns and body of a rule continued with `|`.
+  fn parse_rule_continuation(&mut self) -> ParseResult<Rule> {
+    // "|" pat* = term
+    let mut pats = vec![];
+    self.skip_trivia();
+    while !self.try_consume("=") {
+      pats.push(self.parse_pattern(false)?);
+      self.skip_trivia();
+    }
+    let body = self.parse_term()?;
+    Ok(Rule { pats, body })
+  }
+
+  /// Parses a pattern that must make up the whole input.
+  pub fn parse_single_pattern(&mut self) -> ParseResult<Pattern> {
+    let pat = self.parse_pattern(false)?;
+    self.skip_trivia();
+    if !self.is_eof() {
+      return self.expected("end of pattern");
+    }
+    Ok(pat)
+  }
+
   fn parse_pattern(&mut self, simple: bool) -> ParseResult<Pattern> {
     maybe_grow(|| {
       let (tag, unexpected_tag) = self.parse_tag()?;
@@ -298,7 +375,7 @@ impl<'a> TermParser<'a> {
       self.skip_trivia();
 
       // Lambda, unscoped lambda
-      if self.starts_with("λ") || self.starts_with("@") {
+      if self.starts_with("λ") || self.starts_with("@") || self.starts_with("\\") {
         self.advance_one();
         let tag = tag.unwrap_or(Tag::Static);
         let pat = self.parse_pattern(true)?;
@@ -314,7 +391,10 @@ impl<'a> TermParser<'a> {
         self.skip_trivia();
         let starts_with_oper = self.peek_one().map_or(false, |c| "+-*/%&|<>^=!".contains(c));
         if starts_with_oper {
+          let opr_idx = *self.index();
           let opr = self.parse_oper()?;
+          let is_signed_num =
+            matches!(opr, Op::ADD | Op::SUB) && self.peek_one().is_some_and(|c| c.is_ascii_digit());
 
           // jk, actually a tuple
           self.skip_trivia();
@@ -327,9 +407,29 @@ impl<'a> TermParser<'a> {
             return Ok(Term::Fan { fan: FanKind::Tup, tag: tag.unwrap_or(Tag::Static), els });
           }
 
-          // Opr
           unexpected_tag(self)?;
+
+          // Operator as a function
+          if self.try_consume(")") {
+            return Ok(Term::oper_section(opr, None, None));
+          }
+
           let fst = self.parse_term()?;
+
+          // Right operator section
+          self.skip_trivia();
+          if self.try_consume(")") {
+            // `(-1)` is a signed number, a section needs a space after the operator, like `(- 1)`.
+            if is_signed_num {
+              self.index = opr_idx;
+              let num = self.parse_number()?;
+              self.consume(")")?;
+              return Ok(Term::Num { val: num });
+            }
+            return Ok(Term::oper_section(opr, None, Some(fst)));
+          }
+
+          // Opr
           let snd = self.parse_term()?;
           self.consume(")")?;
           return Ok(Term::Oper { opr, fst: Box::new(fst), snd: Box::new(snd) });
@@ -338,6 +438,19 @@ impl<'a> TermParser<'a> {
         // Tup or App
         let head = self.parse_term()?;
 
+        // Left operator section
+        // `(x *)` is still an application of `x` to an eraser.
+        self.skip_trivia();
+        let ini_idx = *self.index();
+        if self.peek_one().map_or(false, |c| "+-/%&|<>^=!".contains(c)) {
+          let opr = self.parse_oper()?;
+          self.skip_trivia();
+          if self.try_consume(")") {
+            return Ok(Term::oper_section(opr, Some(head), None));
+          }
+          self.index = ini_idx;
+        }
+
         // Tup
         self.skip_trivia();
         if self.starts_with(",") {
@@ -481,7 +594,7 @@ impl<'a> TermParser<'a> {
       if self.try_parse_keyword("match") {
         unexpected_tag(self)?;
         let (bnd, arg, with) = self.parse_match_header()?;
-        let arms = self.list_like(|p| p.parse_match_arm(), "{", "}", ";", false, 1)?;
+        let arms = self.parse_match_arms()?;
         return Ok(Term::Mat { arg: Box::new(arg), bnd, with, arms });
       }
 
@@ -490,7 +603,11 @@ impl<'a> TermParser<'a> {
         unexpected_tag(self)?;
         let (bnd, arg, with) = self.parse_match_header()?;
 
-        self.consume("{")?;
+        let layout = self.try_start_layout_block();
+        if layout.is_none() {
+          self.consume("{")?;
+        }
+        self.expect_layout_arm(layout)?;
         self.try_consume("|");
         self.consume("0")?;
         self.consume(":")?;
@@ -500,13 +617,16 @@ impl<'a> TermParser<'a> {
         let mut arms = vec![zero];
         let mut expected_num = 1;
         loop {
+          self.expect_layout_arm(layout)?;
           self.try_consume("|");
           // case _
-          if self.try_consume("_") {
+          if self.try_consume("_") || self.try_parse_keyword("otherwise") {
             self.consume(":")?;
             arms.push(self.parse_term()?);
             self.try_consume(";");
-            self.consume("}")?;
+            if layout.is_none() {
+              self.consume("}")?;
+            }
             break;
           }
           // case num
@@ -537,7 +657,7 @@ impl<'a> TermParser<'a> {
       if self.try_parse_keyword("fold") {
         unexpected_tag(self)?;
         let (bnd, arg, with) = self.parse_match_header()?;
-        let arms = self.list_like(|p| p.parse_match_arm(), "{", "}", ";", false, 1)?;
+        let arms = self.parse_match_arms()?;
         return Ok(Term::Fold { arg: Box::new(arg), bnd, with, arms });
       }
 
@@ -592,10 +712,39 @@ impl<'a> TermParser<'a> {
       // Var
       unexpected_tag(self)?;
       let nam = self.labelled(|p| p.parse_bend_name(), "term")?;
+
+      // Constructor with named fields
+      if self.starts_with_named_field() {
+        self.skip_trivia();
+        let fields = self.list_like(|p| p.parse_named_field(), "{", "}", ",", true, 0)?;
+        return Ok(Term::CtrFields { ctr: nam, fields });
+      }
+
       Ok(Term::Var { nam })
     })
   }
 
+  /// Whether the next thing is the start of the named fields of a constructor, `{ field:`.
+  fn starts_with_named_field(&mut self) -> bool {
+    let ini_idx = *self.index();
+    self.skip_trivia();
+    let is_named_field = self.try_consume_exactly("{") && {
+      self.skip_trivia();
+      let field = self.parse_bend_name();
+      self.skip_trivia();
+      field.is_ok() && self.starts_with(":")
+    };
+    self.index = ini_idx;
+    is_named_field
+  }
+
+  fn parse_named_field(&mut self) -> ParseResult<(Name, Term)> {
+    let nam = self.parse_bend_name()?;
+    self.consume(":")?;
+    let val = self.parse_term()?;
+    Ok((nam, val))
+  }
+
   fn parse_name_or_era(&mut self) -> ParseResult<Option<Name>> {
     self.labelled(
       |p| {
@@ -638,14 +787,14 @@ impl<'a> TermParser<'a> {
 
   fn parse_match_arg(&mut self) -> ParseResult<(Option<Name>, Term)> {
     let ini_idx = *self.index();
-    let mut arg = self.parse_term()?;
+    let mut arg = self.parse_match_arg_term()?;
     let end_idx = *self.index();
 
     self.skip_trivia();
     match (&mut arg, self.starts_with("=")) {
       (Term::Var { nam }, true) => {
         self.consume("=")?;
-        Ok((Some(std::mem::take(nam)), self.parse_term()?))
+        Ok((Some(std::mem::take(nam)), self.parse_match_arg_term()?))
       }
       (Term::Var { nam }, false) => Ok((Some(nam.clone()), Term::Var { nam: std::mem::take(nam) })),
       (_, true) => self.expected_spanned("argument name", ini_idx, end_idx),
@@ -653,6 +802,22 @@ impl<'a> TermParser<'a> {
     }
   }
 
+  /// A variable followed by the arms is the matched variable, not a constructor with named fields.
+  fn parse_match_arg_term(&mut self) -> ParseResult<Term> {
+    let ini_idx = *self.index();
+    self.skip_trivia();
+    if self.peek_one().is_some_and(|c| c.is_ascii_alphabetic() || c == '_') {
+      if let Ok(nam) = self.parse_bend_name() {
+        self.skip_trivia();
+        if self.starts_with("{") {
+          return Ok(Term::Var { nam });
+        }
+      }
+    }
+    self.index = ini_idx;
+    self.parse_term()
+  }
+
   fn parse_match_header(&mut self) -> ParseResult<(Option<Name>, Term, Vec<Name>)> {
     let (bnd, arg) = self.parse_match_arg()?;
     self.skip_trivia();
@@ -660,7 +825,7 @@ impl<'a> TermParser<'a> {
       self.skip_trivia();
       let mut with = vec![self.parse_bend_name()?];
       self.skip_trivia();
-      while !self.starts_with("{") {
+      while !self.starts_with("{") && !self.starts_with(":") {
         self.try_consume(",");
         self.skip_trivia();
         with.push(self.parse_bend_name()?);
@@ -676,11 +841,65 @@ impl<'a> TermParser<'a> {
   fn parse_match_arm(&mut self) -> ParseResult<MatchRule> {
     self.try_consume("|");
     self.skip_trivia();
-    let nam = self.parse_name_or_era()?;
+    let nam = if self.try_parse_keyword("otherwise") { None } else { self.parse_name_or_era()? };
     self.consume(":")?;
     let bod = self.parse_term()?;
     Ok((nam, vec![], bod))
   }
+
+  /// Parses the arms of a `match` or `fold`, either between braces or as a layout block.
+  fn parse_match_arms(&mut self) -> ParseResult<Vec<MatchRule>> {
+    let Some(indent) = self.try_start_layout_block() else {
+      return self.list_like(|p| p.parse_match_arm(), "{", "}", ";", false, 1);
+    };
+    self.expect_layout_arm(Some(indent))?;
+    let mut arms = vec![self.parse_match_arm()?];
+    self.try_consume(";");
+    while self.in_layout_block(indent) {
+      arms.push(self.parse_match_arm()?);
+      self.try_consume(";");
+    }
+    Ok(arms)
+  }
+
+  /// Layout blocks are opened by a `:` at the end of a `match`, `switch` or `fold` header,
+  /// and contain the arms that follow it indented further than the line of the header.
+  ///
+  /// If a layout block starts here, consumes the `:` and returns the indentation of the header.
+  fn try_start_layout_block(&mut self) -> Option<usize> {
+    self.skip_trivia();
+    let indent = self.line_indent();
+    self.try_consume_exactly(":").then_some(indent)
+  }
+
+  /// Whether the next arm still belongs to the layout block of a header with the given indentation.
+  fn in_layout_block(&mut self, indent: usize) -> bool {
+    self.skip_trivia();
+    let ends_enclosing = matches!(self.peek_one(), None | Some(')' | ']' | '}' | ','));
+    !ends_enclosing && self.column() > indent
+  }
+
+  /// Fails if inside a layout block whose arms ended before the expected ones.
+  fn expect_layout_arm(&mut self, layout: Option<usize>) -> ParseResult<()> {
+    match layout {
+      Some(indent) if !self.in_layout_block(indent) => self.expected("arm indented further than its header"),
+      _ => Ok(()),
+    }
+  }
+
+  /// The column of the current position.
+  fn column(&mut self) -> usize {
+    let idx = *self.index();
+    let line_start = self.input[.. idx].rfind('\n').map_or(0, |i| i + 1);
+    self.input[line_start .. idx].chars().count()
+  }
+
+  /// The indentation of the line of the current position.
+  fn line_indent(&mut self) -> usize {
+    let idx = *self.index();
+    let line_start = self.input[.. idx].rfind('\n').map_or(0, |i| i + 1);
+    self.input[line_start ..].chars().take_while(|c| *c == ' ' || *c == '\t').count()
+  }
 }
 
 impl<'a> Parser<'a> for TermParser<'a> {
@@ -0,0 +1,21 @@
+use super::{Book, Name};
+use hvm::ast::Net;
+
+impl Book {
+  /// Adds an already compiled net to the book as a definition.
+  ///
+  /// The net skips all the compilation passes and goes directly into the generated hvm book,
+  /// so any definition it references must also be present in the program.
+  pub fn import_net(&mut self, name: Name, net: Net) -> Result<(), String> {
+    if self.defs.contains_key(&name) || self.hvm_defs.contains_key(&name) || self.ctrs.contains_key(&name) {
+      return Err(format!("Can't import net '{name}', the book already has a definition with that name."));
+    }
+    self.hvm_defs.insert(name, net);
+    Ok(())
+  }
+}
+
+/// Returns the compiled net of a definition, so that it can be added to other books with [`Book::import_net`].
+pub fn export_net(hvm_book: &hvm::ast::Book, name: &Name) -> Option<Net> {
+  hvm_book.defs.get(name.as_ref()).cloned()
+}
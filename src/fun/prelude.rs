@@ -0,0 +1,41 @@
+use super::{parser::TermParser, Book};
+
+const PRELUDE: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/src/fun/prelude.bend"));
+
+impl Book {
+  /// Returns a book with only the definitions of the prelude.
+  pub fn prelude() -> Book {
+    TermParser::new(PRELUDE)
+      .parse_book(Book::default(), true)
+      .expect("Error parsing prelude file, this should not happen")
+  }
+
+  /// Adds the prelude datatypes and functions to the book.
+  ///
+  /// Returns an error if the book already has something with the same name as a prelude item.
+  pub fn with_prelude(mut self) -> Result<Book, String> {
+    let prelude = Book::prelude();
+
+    for (nam, adt) in prelude.adts {
+      if self.adts.contains_key(&nam) {
+        return Err(format!("'{nam}' is a prelude datatype and should not be redefined."));
+      }
+      for ctr in adt.ctrs.keys() {
+        if self.ctrs.contains_key(ctr) || self.defs.contains_key(ctr) {
+          return Err(format!("'{ctr}' is a prelude constructor and should not be redefined."));
+        }
+        self.ctrs.insert(ctr.clone(), nam.clone());
+      }
+      self.adts.insert(nam, adt);
+    }
+
+    for (nam, def) in prelude.defs {
+      if self.defs.contains_key(&nam) || self.ctrs.contains_key(&nam) {
+        return Err(format!("'{nam}' is a prelude function and should not be redefined."));
+      }
+      self.defs.insert(nam, def);
+    }
+
+    Ok(self)
+  }
+}
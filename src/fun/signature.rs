@@ -0,0 +1,76 @@
+use crate::fun::{
+  builtins::{LIST, STRING},
+  Book, FanKind, Name, Pattern, Term,
+};
+
+/// The arguments expected by a definition, as far as they can be told from its rules.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+  pub args: Vec<ArgKind>,
+}
+
+/// What kind of value an argument of a definition is expected to be.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArgKind {
+  /// Matched against numbers.
+  Num,
+  /// Matched against the constructors of the given type.
+  Adt(Name),
+  /// Destructured as a tuple with the given number of elements.
+  Tup(usize),
+  /// Only bound to a variable, or matched in inconsistent ways, so it could be any term.
+  Any,
+}
+
+impl Signature {
+  pub fn arity(&self) -> usize {
+    self.args.len()
+  }
+}
+
+impl Book {
+  /// Returns the signature of a definition, so that a host calling it knows what arguments to pass.
+  ///
+  /// The kind of each argument is inferred from the patterns of the rules in that position.
+  /// For a definition with a single rule, the lambdas that its body starts with are also counted as arguments.
+  ///
+  /// Precondition: The definition's rules haven't been desugared yet, otherwise every argument is `Any`.
+  pub fn entry_signature(&self, name: &Name) -> Option<Signature> {
+    let def = self.defs.get(name)?;
+    let mut args = (0 .. def.arity())
+      .map(|col| {
+        let kinds = def.rules.iter().filter_map(|rule| rule.pats[col].arg_kind(self));
+        kinds.reduce(|a, b| if a == b { a } else { ArgKind::Any }).unwrap_or(ArgKind::Any)
+      })
+      .collect::<Vec<_>>();
+
+    if let [rule] = def.rules.as_slice() {
+      let mut body = &rule.body;
+      while let Term::Lam { pat, bod, .. } = body {
+        args.push(pat.arg_kind(self).unwrap_or(ArgKind::Any));
+        body = bod;
+      }
+    }
+    Some(Signature { args })
+  }
+}
+
+impl Pattern {
+  /// The kind of argument this pattern accepts, or `None` if it accepts anything.
+  fn arg_kind(&self, book: &Book) -> Option<ArgKind> {
+    match self {
+      // Constructors without fields are only resolved from variables later.
+      Pattern::Var(Some(nam)) if book.ctrs.contains_key(nam) => Some(ArgKind::Adt(book.ctrs[nam].clone())),
+      Pattern::Var(_) | Pattern::Chn(_) => None,
+      Pattern::Num(_) => Some(ArgKind::Num),
+      Pattern::Ctr(nam, _) => match book.ctrs.get(nam) {
+        Some(adt) => Some(ArgKind::Adt(adt.clone())),
+        None => Some(ArgKind::Any),
+      },
+      Pattern::Lst(_) => Some(ArgKind::Adt(Name::new(LIST))),
+      Pattern::Str(_) => Some(ArgKind::Adt(Name::new(STRING))),
+      Pattern::Fan(FanKind::Tup, _, els) => Some(ArgKind::Tup(els.len())),
+      Pattern::Fan(FanKind::Dup, ..) => Some(ArgKind::Any),
+    }
+  }
+}
@@ -38,6 +38,10 @@ pub fn book_to_hvm(book: &Book, diags: &mut Diagnostics) -> Result<(hvm::ast::Bo
     }
   }
 
+  for (name, net) in book.hvm_defs.iter() {
+    hvm_book.defs.insert(name.to_string(), net.clone());
+  }
+
   labels.con.finish();
   labels.dup.finish();
 
@@ -224,6 +228,7 @@ impl<'t, 'l> EncodeTermState<'t, 'l> {
         | Term::Bend { .. } // Removed in desugar_bend
         | Term::Fold { .. } // Removed in desugar_fold
         | Term::Open { .. } // Removed in desugar_open
+        | Term::CtrFields { .. } // Removed in desugar_ctr_fields
         | Term::Nat { .. } // Removed in encode_nat
         | Term::Str { .. } // Removed in encode_str
         | Term::List { .. } // Removed in encode_list
@@ -0,0 +1,65 @@
+use crate::{
+  fun::{Book, FanKind, Pattern, Tag, Term},
+  maybe_grow,
+};
+
+impl Book {
+  /// Replaces the duplications of superpositions with the same label by direct bindings,
+  /// since they would annihilate as soon as they meet at runtime.
+  ///
+  /// Duplications and superpositions with different labels commute instead of annihilating,
+  /// so they're left as they are. Static labels are given a new label each,
+  /// so two static fans are never known to have the same label.
+  ///
+  /// The binds are introduced one after the other, so the duplication is kept if
+  /// an element of the superposition uses a variable with the same name as one of the previous binds.
+  ///
+  /// Example:
+  /// ```bend
+  /// let {x1 x2} = {1 2}; (x1 x2)
+  ///
+  /// // Transforms to:
+  /// let x1 = 1; let x2 = 2; (x1 x2)
+  /// ```
+  pub fn annihilate_dup_sups(&mut self) {
+    for def in self.defs.values_mut() {
+      for rule in def.rules.iter_mut() {
+        rule.body.annihilate_dup_sups();
+      }
+    }
+  }
+}
+
+impl Term {
+  pub fn annihilate_dup_sups(&mut self) {
+    maybe_grow(|| {
+      if let Term::Let { pat: box Pattern::Fan(FanKind::Dup, dup_tag, binds), val, nxt } = self
+        && let Term::Fan { fan: FanKind::Dup, tag: sup_tag, els } = val.as_mut()
+        && dup_tag == sup_tag
+        && *dup_tag != Tag::Static
+        && binds.len() == els.len()
+        && !captures_later_els(binds, els)
+      {
+        let binds = std::mem::take(binds);
+        let els = std::mem::take(els);
+        let mut term = std::mem::take(nxt.as_mut());
+        for (bind, el) in binds.into_iter().zip(els).rev() {
+          term = Term::Let { pat: Box::new(bind), val: Box::new(el), nxt: Box::new(term) };
+        }
+        *self = term;
+      }
+
+      for child in self.children_mut() {
+        child.annihilate_dup_sups();
+      }
+    })
+  }
+}
+
+/// Whether binding the patterns in order would capture a variable used by one of the following elements.
+fn captures_later_els(binds: &[Pattern], els: &[Term]) -> bool {
+  els.iter().enumerate().skip(1).any(|(i, el)| {
+    let free_vars = el.free_vars();
+    binds[.. i].iter().flat_map(Pattern::binds).flatten().any(|bind| free_vars.contains_key(bind))
+  })
+}
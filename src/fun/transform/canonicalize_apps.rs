@@ -0,0 +1,55 @@
+use crate::{
+  fun::{Book, Term},
+  maybe_grow,
+};
+
+impl Book {
+  /// Joins application spines that are split by a `let` or `use` in the function position,
+  /// so that equivalent applications like `((let x = v; f) a)` and `let x = v; (f a)` end up being the same term.
+  ///
+  /// After this, every application spine is left-nested all the way to its head.
+  /// The binding is only moved out if it doesn't capture variables of the argument.
+  pub fn canonicalize_apps(&mut self) {
+    for def in self.defs.values_mut() {
+      for rule in def.rules.iter_mut() {
+        rule.body.canonicalize_apps();
+      }
+    }
+  }
+}
+
+impl Term {
+  pub fn canonicalize_apps(&mut self) {
+    maybe_grow(|| {
+      for child in self.children_mut() {
+        child.canonicalize_apps();
+      }
+    });
+    self.float_binding_from_fun();
+  }
+
+  /// `((let p = v; f) a)` => `let p = v; (f a)`
+  fn float_binding_from_fun(&mut self) {
+    maybe_grow(|| {
+      let Term::App { fun, arg, .. } = self else { return };
+      let arg_vars = arg.free_vars();
+      let captures = match fun.as_ref() {
+        Term::Let { pat, .. } => pat.binds().flatten().any(|bind| arg_vars.contains_key(bind)),
+        Term::Use { nam, .. } => nam.as_ref().is_some_and(|nam| arg_vars.contains_key(nam)),
+        _ => return,
+      };
+      if captures {
+        return;
+      }
+
+      let mut bind = std::mem::take(fun.as_mut());
+      let (Term::Let { nxt, .. } | Term::Use { nxt, .. }) = &mut bind else { unreachable!() };
+      std::mem::swap(fun.as_mut(), nxt.as_mut());
+      let app = std::mem::replace(self, bind);
+      let (Term::Let { nxt, .. } | Term::Use { nxt, .. }) = self else { unreachable!() };
+      **nxt = app;
+      // The function of the binding may itself be another binding.
+      nxt.float_binding_from_fun();
+    })
+  }
+}
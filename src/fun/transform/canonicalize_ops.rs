@@ -0,0 +1,61 @@
+use crate::{
+  fun::{Book, Num, Op, Term},
+  maybe_grow,
+};
+
+impl Book {
+  /// Reorders the operands of commutative operations into a canonical order,
+  /// so that equivalent operations like `(+ x 1)` and `(+ 1 x)` end up being the same term.
+  ///
+  /// Number literals come first and the remaining operands are ordered by how they're displayed.
+  /// The operands of non-commutative operations are never reordered,
+  /// and the ones of bitwise operations only when both are integer literals, since on floats they aren't commutative.
+  pub fn canonicalize_ops(&mut self) {
+    for def in self.defs.values_mut() {
+      for rule in def.rules.iter_mut() {
+        rule.body.canonicalize_ops();
+      }
+    }
+  }
+}
+
+impl Term {
+  pub fn canonicalize_ops(&mut self) {
+    maybe_grow(|| {
+      for child in self.children_mut() {
+        child.canonicalize_ops();
+      }
+    });
+
+    if let Term::Oper { opr, fst, snd } = self {
+      let commutative = opr.is_commutative()
+        || (opr.is_commutative_on_integers() && is_integer_lit(fst) && is_integer_lit(snd));
+      if commutative && operand_key(snd) < operand_key(fst) {
+        std::mem::swap(fst, snd);
+      }
+    }
+  }
+}
+
+fn operand_key(term: &Term) -> (bool, String) {
+  (!matches!(term, Term::Num { .. }), term.to_string())
+}
+
+fn is_integer_lit(term: &Term) -> bool {
+  matches!(term, Term::Num { val: Num::U24(_) | Num::I24(_) })
+}
+
+impl Op {
+  /// Whether the order of the operands doesn't change the result of the operation, for any type of number.
+  pub fn is_commutative(&self) -> bool {
+    matches!(self, Op::ADD | Op::MUL | Op::EQ | Op::NEQ)
+  }
+
+  /// Whether the order of the operands doesn't change the result of the operation on integers.
+  ///
+  /// On floats, these are `atan2`, `log` and `pow`, so they can only be reordered
+  /// when both operands are known to be integers.
+  pub fn is_commutative_on_integers(&self) -> bool {
+    matches!(self, Op::AND | Op::OR | Op::XOR)
+  }
+}
@@ -0,0 +1,84 @@
+use crate::{
+  fun::{Book, Name, Op, Term},
+  maybe_grow,
+};
+
+pub(crate) const BOOL: &str = "Bool";
+pub(crate) const TRUE: &str = "Bool/True";
+pub(crate) const FALSE: &str = "Bool/False";
+
+impl Book {
+  /// Converts the numeric results of comparisons that are matched with `match` into the `Bool` type of the book.
+  ///
+  /// Only done when the book declares `type Bool = True | False`,
+  /// and only for comparisons that are directly the argument of a `match` with `Bool` arms.
+  /// Every other comparison is kept as a number, since its result can be used as one later,
+  /// like when it's bound with `let`, returned, used in an `if` or `switch` or in another numeric operation.
+  ///
+  /// Precondition: References have been resolved and match terms not yet fixed.
+  ///
+  /// Example:
+  /// ```bend
+  /// match (< a b) {
+  ///   Bool/True: A
+  ///   Bool/False: B
+  /// }
+  ///
+  /// // Transforms to:
+  /// match switch %cmp = (< a b) { 0: Bool/False; _: Bool/True } {
+  ///   Bool/True: A
+  ///   Bool/False: B
+  /// }
+  /// ```
+  pub fn comparisons_to_bool(&mut self) {
+    if !self.declares_bool() {
+      return;
+    }
+    for def in self.defs.values_mut() {
+      for rule in def.rules.iter_mut() {
+        rule.body.comparisons_to_bool();
+      }
+    }
+  }
+
+  /// Whether the book declares `type Bool = True | False`.
+  pub(crate) fn declares_bool(&self) -> bool {
+    self.adts.get(&Name::new(BOOL)).is_some_and(|adt| {
+      adt.ctrs.len() == 2
+        && [FALSE, TRUE]
+          .iter()
+          .all(|ctr| adt.ctrs.get(&Name::new(*ctr)).is_some_and(|fields| fields.is_empty()))
+    })
+  }
+}
+
+impl Term {
+  fn comparisons_to_bool(&mut self) {
+    maybe_grow(|| {
+      for child in self.children_mut() {
+        child.comparisons_to_bool();
+      }
+
+      if let Term::Mat { arg, arms, .. } = self {
+        let matches_bool =
+          arms.iter().any(|(ctr, ..)| ctr.as_ref().is_some_and(|ctr| ctr == TRUE || ctr == FALSE));
+        if matches_bool && matches!(arg.as_ref(), Term::Oper { opr, .. } if opr.is_comparison()) {
+          let cmp = std::mem::take(arg.as_mut());
+          **arg = Term::Swt {
+            arg: Box::new(cmp),
+            bnd: Some(Name::new("%cmp")),
+            with: vec![],
+            pred: Some(Name::new("%cmp-1")),
+            arms: vec![Term::r#ref(FALSE), Term::r#ref(TRUE)],
+          };
+        }
+      }
+    })
+  }
+}
+
+impl Op {
+  fn is_comparison(&self) -> bool {
+    matches!(self, Op::EQ | Op::NEQ | Op::LT | Op::GT)
+  }
+}
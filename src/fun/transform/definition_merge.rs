@@ -36,12 +36,13 @@ impl Book {
 
       // Builtin origin takes precedence
       let builtin = equal_defs.iter().any(|nam| self.defs[nam].builtin);
+      let io = equal_defs.iter().any(|nam| self.defs[nam].io);
 
       if equal_defs.len() > 1 {
         // Merging some defs
         // Add the merged def
         let new_def =
-          Definition { name: new_name.clone(), rules: vec![Rule { pats: vec![], body: term }], builtin };
+          Definition { name: new_name.clone(), rules: vec![Rule { pats: vec![], body: term }], builtin, io };
         self.defs.insert(new_name.clone(), new_def);
         // Remove the old ones and write the map of old names to new ones.
         for name in equal_defs {
@@ -1,6 +1,7 @@
 use crate::{
   diagnostics::WarningType,
   fun::{Book, Ctx, Name, Term},
+  hvm::net_refs,
   maybe_grow,
 };
 use std::collections::{hash_map::Entry, HashMap};
@@ -107,10 +108,20 @@ impl Book {
     if let Entry::Vacant(e) = uses.entry(def_name.clone()) {
       e.insert(used);
 
-      // This needs to be done for each rule in case the pass it's ran from has not encoded the pattern match
-      // E.g.: the `flatten_rules` golden test
-      for rule in &self.defs[def_name].rules {
-        self.find_used_definitions(&rule.body, used, uses);
+      if let Some(net) = self.hvm_defs.get(def_name) {
+        // Precompiled nets can also reference other definitions.
+        for nam in net_refs(net) {
+          let nam = Name::new(nam);
+          if self.defs.contains_key(&nam) || self.hvm_defs.contains_key(&nam) {
+            self.insert_used(&nam, used, uses);
+          }
+        }
+      } else {
+        // This needs to be done for each rule in case the pass it's ran from has not encoded the pattern match
+        // E.g.: the `flatten_rules` golden test
+        for rule in &self.defs[def_name].rules {
+          self.find_used_definitions(&rule.body, used, uses);
+        }
       }
     }
   }
@@ -0,0 +1,70 @@
+use crate::{
+  diagnostics::Diagnostics,
+  fun::{Adts, Constructors, Ctx, Name, Term},
+  maybe_grow,
+};
+
+impl Ctx<'_> {
+  /// Converts the constructors applied to named fields into positional applications,
+  /// in the order the fields were declared in the constructor's type.
+  ///
+  /// Done after parsing so that a constructor can be used before its type is declared.
+  pub fn desugar_ctr_fields(&mut self) -> Result<(), Diagnostics> {
+    self.info.start_pass();
+
+    for def in self.book.defs.values_mut() {
+      for rule in def.rules.iter_mut() {
+        if let Err(err) = rule.body.desugar_ctr_fields(&self.book.ctrs, &self.book.adts) {
+          self.info.add_rule_error(err, def.name.clone());
+        }
+      }
+    }
+
+    self.info.fatal(())
+  }
+}
+
+impl Term {
+  fn desugar_ctr_fields(&mut self, ctrs: &Constructors, adts: &Adts) -> Result<(), String> {
+    maybe_grow(|| {
+      for child in self.children_mut() {
+        child.desugar_ctr_fields(ctrs, adts)?;
+      }
+      if let Term::CtrFields { ctr, fields } = self {
+        let Some(adt) = ctrs.get(&*ctr).and_then(|typ| adts.get(typ)) else {
+          return Err(format!("Named fields given to '{ctr}', which is not a constructor."));
+        };
+        let names = adt.ctrs[&*ctr].iter().map(|field| field.nam.clone()).collect::<Vec<_>>();
+        *self = order_named_fields(std::mem::take(ctr), &names, std::mem::take(fields))?;
+      }
+      Ok(())
+    })
+  }
+}
+
+/// Applies a constructor to the values of its named fields, in the order they were declared.
+///
+/// The fields that are not given become the arguments of a lambda, so `Foo { b: 2 }` becomes `λ%a (Foo %a 2)`.
+fn order_named_fields(ctr: Name, fields: &[Name], mut vals: Vec<(Name, Term)>) -> Result<Term, String> {
+  let mut args = vec![];
+  let mut missing = vec![];
+  for field in fields {
+    match vals.iter().position(|(nam, _)| nam == field) {
+      Some(idx) => args.push(vals.remove(idx).1),
+      None => {
+        let var = Name::new(format!("%{field}"));
+        args.push(Term::Var { nam: var.clone() });
+        missing.push(Some(var));
+      }
+    }
+  }
+  if let Some((nam, _)) = vals.first() {
+    let msg = if fields.contains(nam) {
+      format!("Repeated field '{nam}' of constructor '{ctr}'.")
+    } else {
+      format!("Constructor '{ctr}' has no field '{nam}'.")
+    };
+    return Err(msg);
+  }
+  Ok(Term::rfold_lams(Term::call(Term::Var { nam: ctr }, args), missing.into_iter()))
+}
@@ -10,7 +10,7 @@ impl Ctx<'_> {
   pub fn desugar_do_blocks(&mut self) -> Result<(), Diagnostics> {
     self.info.start_pass();
 
-    let def_names = self.book.defs.keys().cloned().collect::<HashSet<_>>();
+    let def_names = self.book.defs.keys().chain(self.book.hvm_defs.keys()).cloned().collect::<HashSet<_>>();
 
     for def in self.book.defs.values_mut() {
       for rule in def.rules.iter_mut() {
@@ -536,7 +536,14 @@ impl std::fmt::Display for DesugarMatchDefErr {
           f,
           "Type mismatch in pattern matching rule. Expected a constructor of type '{}', found '{}' with type '{}'.",
           expected, pat, found
-        )
+        )?;
+        if matches!((expected, found), (Type::Num, Type::Adt(_)) | (Type::Adt(_), Type::Num)) {
+          write!(
+            f,
+            "\nNumbers and constructors can't be told apart at runtime, so they can't be matched in the same argument.\nTo accept both, wrap the numbers in a constructor of a type like 'type Value = (Num val) | (Other val)'."
+          )?;
+        }
+        Ok(())
       }
       DesugarMatchDefErr::NumMissingDefault => {
         write!(f, "Non-exhaustive pattern matching rule. Default case of number type not covered.")
@@ -17,7 +17,7 @@ impl Book {
         };
 
         let rules = vec![Rule { pats: vec![], body }];
-        let def = Definition { name: ctr_name.clone(), rules, builtin: adt.builtin };
+        let def = Definition { name: ctr_name.clone(), rules, builtin: adt.builtin, io: false };
         defs.push((ctr_name.clone(), def));
       }
     }
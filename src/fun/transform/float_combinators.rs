@@ -1,5 +1,6 @@
 use crate::{
   fun::{Book, Definition, Name, Pattern, Rule, Term},
+  hvm::net_refs,
   maybe_grow, multi_iterator,
 };
 use std::collections::{BTreeMap, HashSet};
@@ -108,7 +109,7 @@ impl Term {
     let extracted_term = std::mem::replace(self, comb_ref);
 
     let rules = vec![Rule { body: extracted_term, pats: Vec::new() }];
-    let rule = Definition { name: comb_name.clone(), rules, builtin };
+    let rule = Definition { name: comb_name.clone(), rules, builtin, io: false };
     ctx.combinators.insert(comb_name, (is_safe, rule));
   }
 }
@@ -119,7 +120,7 @@ impl Term {
   /// - A Tuple or Superposition where all elements are safe.
   /// - An application or numeric operation where all arguments are safe.
   /// - A safe Lambda, e.g. a nullary constructor or a lambda with safe body.
-  /// - A Reference with a safe body.
+  /// - A Reference with a safe body, or to a precompiled net without references.
   /// A reference to a recursive definition (or mutually recursive) is not safe.
   fn is_safe(&self, ctx: &mut FloatCombinatorsCtx) -> bool {
     maybe_grow(|| match self {
@@ -148,6 +149,9 @@ impl Term {
           ref_safe
         } else if let Some((safe, _)) = ctx.combinators.get(nam) {
           *safe
+        } else if let Some(net) = ctx.book.hvm_defs.get(nam) {
+          // A precompiled net that doesn't reference other definitions can't expand infinitely.
+          net_refs(net).is_empty()
         } else {
           false
         };
@@ -210,6 +214,7 @@ impl Term {
       | Term::Do { .. }
       | Term::Ask { .. }
       | Term::Open { .. }
+      | Term::CtrFields { .. }
       | Term::Err => unreachable!(),
     }
   }
@@ -255,7 +260,12 @@ impl Term {
       | Term::Ref { .. }
       | Term::Era
       | Term::Err => FloatIter::Zero([]),
-      Term::Do { .. } | Term::Ask { .. } | Term::Bend { .. } | Term::Fold { .. } | Term::Open { .. } => {
+      Term::Do { .. }
+      | Term::Ask { .. }
+      | Term::Bend { .. }
+      | Term::Fold { .. }
+      | Term::Open { .. }
+      | Term::CtrFields { .. } => {
         unreachable!()
       }
     }
@@ -0,0 +1,110 @@
+use crate::{
+  fun::{Book, Definition, FanKind, Name, Rule, Tag, Term},
+  maybe_grow,
+};
+use indexmap::IndexMap;
+
+impl Book {
+  /// Moves constants that are repeated across the book into a single generated definition,
+  /// replacing each occurrence with a reference to it.
+  ///
+  /// A constant is a closed term built only from numbers, erasers, constructors,
+  /// tuples, applications and numeric operations, like `(List/Cons 1 (List/Cons 2 List/Nil))`.
+  /// Only the largest constants with at least `min_size` nodes that appear more than once are hoisted,
+  /// so that each one is compiled only once instead of at every place where it's used.
+  ///
+  /// The new definitions are named after the first definition where their constant appears,
+  /// skipping the names that are already taken by other definitions.
+  ///
+  /// Precondition: References have been resolved.
+  pub fn hoist_constants(&mut self, min_size: usize) {
+    // How many times each constant appears, and the definition where it's first found.
+    let mut found = IndexMap::<Term, (Name, usize)>::new();
+    for def in self.defs.values().filter(|def| !def.builtin) {
+      for rule in def.rules.iter() {
+        rule.body.find_constants(self, min_size, &def.name, &mut found);
+      }
+    }
+
+    let mut name_gen = IndexMap::<Name, usize>::new();
+    let mut hoisted = IndexMap::new();
+    for (term, (def_name, count)) in found {
+      if count > 1 {
+        let idx = name_gen.entry(def_name.clone()).or_default();
+        let nam = loop {
+          let nam = Name::new(format!("{def_name}__K{idx}"));
+          *idx += 1;
+          if !self.defs.contains_key(&nam) && !self.hvm_defs.contains_key(&nam) {
+            break nam;
+          }
+        };
+        hoisted.insert(term, nam);
+      }
+    }
+    if hoisted.is_empty() {
+      return;
+    }
+
+    for def in self.defs.values_mut().filter(|def| !def.builtin) {
+      for rule in def.rules.iter_mut() {
+        rule.body.hoist_constants(&hoisted);
+      }
+    }
+    for (term, nam) in hoisted {
+      let rules = vec![Rule { pats: vec![], body: term }];
+      self.defs.insert(nam.clone(), Definition { name: nam, rules, builtin: false, io: false });
+    }
+  }
+}
+
+impl Term {
+  /// Counts the largest constants inside this term that are big enough to be hoisted.
+  fn find_constants(
+    &self,
+    book: &Book,
+    min_size: usize,
+    def_name: &Name,
+    found: &mut IndexMap<Term, (Name, usize)>,
+  ) {
+    maybe_grow(|| {
+      if self.constant_size(book).is_some_and(|size| size >= min_size) {
+        found.entry(self.clone()).or_insert((def_name.clone(), 0)).1 += 1;
+      } else {
+        for child in self.children() {
+          child.find_constants(book, min_size, def_name, found);
+        }
+      }
+    })
+  }
+
+  fn hoist_constants(&mut self, hoisted: &IndexMap<Term, Name>) {
+    maybe_grow(|| {
+      if let Some(nam) = hoisted.get(self) {
+        *self = Term::Ref { nam: nam.clone() };
+      } else {
+        for child in self.children_mut() {
+          child.hoist_constants(hoisted);
+        }
+      }
+    })
+  }
+
+  /// The number of nodes of the term, if it's a constant.
+  fn constant_size(&self, book: &Book) -> Option<usize> {
+    maybe_grow(|| {
+      match self {
+        Term::Num { .. } | Term::Era => return Some(1),
+        Term::Ref { nam } if book.ctrs.contains_key(nam) => return Some(1),
+        Term::App { tag: Tag::Static, .. }
+        | Term::Fan { fan: FanKind::Tup, tag: Tag::Static, .. }
+        | Term::Oper { .. } => {}
+        _ => return None,
+      }
+      let mut size = 1;
+      for child in self.children() {
+        size += child.constant_size(book)?;
+      }
+      Some(size)
+    })
+  }
+}
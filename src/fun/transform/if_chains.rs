@@ -0,0 +1,107 @@
+use crate::{
+  fun::{Book, Name, Num, Op, Term},
+  maybe_grow,
+};
+
+impl Book {
+  /// Converts chains of `if`s comparing the same variable against successive numbers into a single `switch`.
+  ///
+  /// Precondition: Match terms not yet fixed, so that the switches still have their original args.
+  ///
+  /// Example:
+  /// ```bend
+  /// switch (== x 0) {
+  ///   0: switch (== x 1) {
+  ///     0: C
+  ///     _: B
+  ///   }
+  ///   _: A
+  /// }
+  ///
+  /// // Transforms to:
+  /// switch x {
+  ///   0: A
+  ///   1: B
+  ///   _: C
+  /// }
+  /// ```
+  /// Only chains with at least two comparisons, starting from 0, are converted.
+  /// The last `else` can't use the variable, since the `switch` only gives back its value as a `u24`,
+  /// which isn't the original value if it was an `i24` or `f24`.
+  pub fn convert_if_chains(&mut self) {
+    for def in self.defs.values_mut() {
+      for rule in def.rules.iter_mut() {
+        rule.body.convert_if_chains();
+      }
+    }
+  }
+}
+
+impl Term {
+  pub fn convert_if_chains(&mut self) {
+    maybe_grow(|| {
+      if let Some((var, len)) = self.if_chain() {
+        let mut arms = vec![];
+        let mut cur = std::mem::take(self);
+        for _ in 0 .. len {
+          let Term::Swt { arms: if_arms, .. } = &mut cur else { unreachable!() };
+          arms.push(if_arms.pop().unwrap());
+          cur = if_arms.pop().unwrap();
+        }
+        arms.push(cur);
+        *self = Term::Swt {
+          arg: Box::new(Term::Var { nam: var.clone() }),
+          bnd: Some(var),
+          with: vec![],
+          pred: Some(Name::new(format!("%pred-{len}"))),
+          arms,
+        };
+      }
+
+      for child in self.children_mut() {
+        child.convert_if_chains();
+      }
+    })
+  }
+
+  /// If this term starts a chain of ifs comparing a variable with successive numbers,
+  /// returns the variable and how many comparisons are in the chain.
+  fn if_chain(&self) -> Option<(Name, u32)> {
+    let mut chain_var = None;
+    let mut len = 0;
+    let mut cur = self;
+    while let Some((var, num)) = cur.if_eq_num()
+      && num == len
+      && chain_var.as_ref().map_or(true, |chain_var| chain_var == var)
+    {
+      chain_var = Some(var.clone());
+      len += 1;
+      let Term::Swt { arms, .. } = cur else { unreachable!() };
+      cur = &arms[0];
+    }
+    let var = chain_var.filter(|var| len >= 2 && !cur.free_vars().contains_key(var))?;
+    Some((var, len))
+  }
+
+  /// If this term is an `if` that compares a variable with a number, returns them.
+  fn if_eq_num(&self) -> Option<(&Name, u32)> {
+    let Term::Swt { arg: box Term::Oper { opr: Op::EQ, fst, snd }, bnd, with, pred, arms } = self else {
+      return None;
+    };
+    if !with.is_empty() || arms.len() != 2 {
+      return None;
+    }
+    // The result of the comparison must not be used in the arms.
+    for arm in arms {
+      let free_vars = arm.free_vars();
+      if [bnd, pred].into_iter().flatten().any(|nam| free_vars.contains_key(nam)) {
+        return None;
+      }
+    }
+    match (fst.as_ref(), snd.as_ref()) {
+      (Term::Var { nam }, Term::Num { val: Num::U24(num) })
+      | (Term::Num { val: Num::U24(num) }, Term::Var { nam }) => Some((nam, *num)),
+      _ => None,
+    }
+  }
+}
@@ -0,0 +1,41 @@
+use crate::{fun::Book, hvm::net_refs};
+use std::collections::HashMap;
+
+impl Book {
+  /// Inlines the trivial definitions, whose body is a numeric constant or the identity function,
+  /// removing them from the book. See [`crate::fun::Rule::is_trivial`].
+  ///
+  /// A constant is a number or an operation on other constants, like `pi = 3` or `mask = (- (<< 1 8) 1)`.
+  /// Since these definitions don't reference anything and are small, copying them to every place
+  /// where they're used saves a call without making the program bigger.
+  ///
+  /// Precondition: References have been resolved and match definitions desugared.
+  pub fn inline_constants(&mut self) {
+    let used_by_nets = self.hvm_defs.values().flat_map(net_refs).collect::<Vec<_>>();
+    let consts = self
+      .defs
+      .values()
+      .filter_map(|def| match def.rules.as_slice() {
+        [rule] if rule.pats.is_empty() && rule.is_trivial() => Some((def, &rule.body)),
+        _ => None,
+      })
+      .filter(|(def, _)| {
+        !def.builtin
+          && self.entrypoint.as_ref() != Some(&def.name)
+          && !used_by_nets.contains(&def.name.as_ref())
+      })
+      .map(|(def, body)| (def.name.clone(), body.clone()))
+      .collect::<HashMap<_, _>>();
+
+    if consts.is_empty() {
+      return;
+    }
+
+    self.defs.retain(|nam, _| !consts.contains_key(nam));
+    for def in self.defs.values_mut() {
+      for rule in def.rules.iter_mut() {
+        rule.body.inline_refs(&mut |nam| consts.get(nam).cloned());
+      }
+    }
+  }
+}
@@ -0,0 +1,57 @@
+use crate::fun::{Book, Name, Term};
+use std::collections::HashMap;
+
+impl Book {
+  /// Inlines the references to pure nullary definitions in the body of `def_name`, leaving the rest of the book as it is.
+  ///
+  /// A definition is pure and nullary if it has a single rule without patterns,
+  /// isn't recursive, isn't builtin and doesn't perform IO or use unscoped variables.
+  /// Only the references written in the target are inlined; the ones in the inlined bodies are kept.
+  ///
+  /// Useful for optimizing a single hot definition without changing the others.
+  ///
+  /// Precondition: References have been resolved and match definitions desugared.
+  ///
+  /// Example:
+  /// ```bend
+  /// one = 1
+  /// inc = λx (+ x one)
+  /// main = (inc 2)
+  ///
+  /// // Inlining in `main` transforms it to:
+  /// main = (λx (+ x one) 2)
+  /// ```
+  pub fn inline_pure_nullary_refs_in(&mut self, def_name: &Name) -> Result<(), String> {
+    let Some(def) = self.defs.get(def_name) else {
+      return Err(format!("Definition '{def_name}' not found."));
+    };
+
+    let recursive = self.recursive_defs();
+    let mut bodies = HashMap::new();
+    for nam in def.rules.iter().flat_map(|rule| rule.body.collect_refs()) {
+      if &nam != def_name
+        && !recursive.contains(&nam)
+        && let Some(body) = self.pure_nullary_body(&nam)
+      {
+        bodies.insert(nam, body.clone());
+      }
+    }
+
+    let def = self.defs.get_mut(def_name).unwrap();
+    for rule in def.rules.iter_mut() {
+      rule.body.inline_refs(&mut |nam| bodies.get(nam).cloned());
+    }
+    Ok(())
+  }
+
+  /// The body of a non-recursive definition, if it can be inlined by `inline_pure_nullary_refs_in`.
+  fn pure_nullary_body(&self, def_name: &Name) -> Option<&Term> {
+    let def = self.defs.get(def_name)?;
+    match def.rules.as_slice() {
+      [rule] if rule.pats.is_empty() && !def.builtin && !def.io && !rule.body.has_unscoped() => {
+        Some(&rule.body)
+      }
+      _ => None,
+    }
+  }
+}
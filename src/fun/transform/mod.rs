@@ -1,7 +1,12 @@
+pub mod annihilate_dup_sups;
 pub mod apply_args;
+pub mod canonicalize_apps;
+pub mod canonicalize_ops;
+pub mod comparisons_to_bool;
 pub mod definition_merge;
 pub mod definition_pruning;
 pub mod desugar_bend;
+pub mod desugar_ctr_fields;
 pub mod desugar_do_blocks;
 pub mod desugar_fold;
 pub mod desugar_match_defs;
@@ -13,9 +18,18 @@ pub mod expand_generated;
 pub mod fix_match_defs;
 pub mod fix_match_terms;
 pub mod float_combinators;
+pub mod hoist_constants;
+pub mod if_chains;
+pub mod inline_constants;
+pub mod inline_nullary_refs;
 pub mod linearize_matches;
 pub mod linearize_vars;
+pub mod nats_to_nums;
+pub mod rename_channels;
 pub mod resolve_refs;
 pub mod resugar_list;
 pub mod resugar_string;
+pub mod tail_recursion;
+pub mod tautological_comparisons;
+pub mod unfold_fully;
 pub mod unique_names;
@@ -0,0 +1,70 @@
+use crate::{
+  fun::{
+    builtins::{NAT_SUCC, NAT_ZERO},
+    Book, Num, Term,
+  },
+  maybe_grow,
+};
+
+const U24_MAX: u32 = 0xFFFFFF;
+
+impl Book {
+  /// Replaces the natural numbers built with `Nat/Succ` and `Nat/Zero` that are used as operands
+  /// of numeric operations with the native numbers they stand for.
+  ///
+  /// A numeric operation on a `Nat` value doesn't compute anything meaningful,
+  /// so a `Nat` used as an operand can only mean its number, which native arithmetic handles much faster.
+  /// Only closed naturals are converted, so `(Nat/Succ x)` is kept as it is.
+  ///
+  /// Precondition: Constructors have been encoded as definitions and references resolved.
+  ///
+  /// Example:
+  /// ```bend
+  /// (+ (Nat/Succ (Nat/Succ Nat/Zero)) 1)
+  ///
+  /// // Transforms to:
+  /// (+ 2 1)
+  /// ```
+  pub fn nats_to_nums(&mut self) {
+    for def in self.defs.values_mut() {
+      for rule in def.rules.iter_mut() {
+        rule.body.nats_to_nums();
+      }
+    }
+  }
+}
+
+impl Term {
+  fn nats_to_nums(&mut self) {
+    maybe_grow(|| {
+      if let Term::Oper { fst, snd, .. } = self {
+        for operand in [fst, snd] {
+          if let Some(val) = operand.as_nat() {
+            **operand = Term::Num { val: Num::U24(val) };
+          }
+        }
+      }
+      for child in self.children_mut() {
+        child.nats_to_nums();
+      }
+    })
+  }
+
+  /// The value of the term if it's a natural number that fits in a native number.
+  fn as_nat(&self) -> Option<u32> {
+    let mut succs = 0u32;
+    let mut term = self;
+    let val = loop {
+      match term {
+        Term::Nat { val } => break succs.checked_add(*val)?,
+        Term::Ref { nam } if nam == NAT_ZERO => break succs,
+        Term::App { fun: box Term::Ref { nam }, arg, .. } if nam == NAT_SUCC => {
+          succs += 1;
+          term = arg;
+        }
+        _ => return None,
+      }
+    };
+    (val <= U24_MAX).then_some(val)
+  }
+}
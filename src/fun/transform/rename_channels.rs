@@ -0,0 +1,48 @@
+use crate::{
+  fun::{Book, Name, Pattern, Term},
+  maybe_grow,
+};
+
+impl Book {
+  /// Adds `prefix` to the name of every channel in the book, both where it's bound and where it's used,
+  /// so that the scopeless variables of books coming from different sources can't collide when they're merged.
+  pub fn with_renamed_channels(&mut self, prefix: &str) {
+    for def in self.defs.values_mut() {
+      for rule in def.rules.iter_mut() {
+        for pat in rule.pats.iter_mut() {
+          pat.rename_channels(prefix);
+        }
+        rule.body.rename_channels(prefix);
+      }
+    }
+  }
+}
+
+impl Term {
+  fn rename_channels(&mut self, prefix: &str) {
+    maybe_grow(|| {
+      if let Term::Link { nam } = self {
+        *nam = Name::new(format!("{prefix}{nam}"));
+      }
+      if let Some(pat) = self.pattern_mut() {
+        pat.rename_channels(prefix);
+      }
+      for child in self.children_mut() {
+        child.rename_channels(prefix);
+      }
+    })
+  }
+}
+
+impl Pattern {
+  fn rename_channels(&mut self, prefix: &str) {
+    maybe_grow(|| {
+      if let Pattern::Chn(nam) = self {
+        *nam = Name::new(format!("{prefix}{nam}"));
+      }
+      for child in self.children_mut() {
+        child.rename_channels(prefix);
+      }
+    })
+  }
+}
@@ -1,6 +1,6 @@
 use crate::{
   diagnostics::Diagnostics,
-  fun::{Ctx, Name, Pattern, Term},
+  fun::{Ctx, FanKind, Name, Pattern, Tag, Term},
   maybe_grow,
 };
 use std::collections::{HashMap, HashSet};
@@ -20,7 +20,7 @@ impl Ctx<'_> {
   pub fn resolve_refs(&mut self) -> Result<(), Diagnostics> {
     self.info.start_pass();
 
-    let def_names = self.book.defs.keys().cloned().collect::<HashSet<_>>();
+    let def_names = self.book.defs.keys().chain(self.book.hvm_defs.keys()).cloned().collect::<HashSet<_>>();
     for (def_name, def) in &mut self.book.defs {
       for rule in def.rules.iter_mut() {
         let mut scope = HashMap::new();
@@ -59,6 +59,8 @@ impl Term {
         // If the variable is actually a reference to a function, swap the term.
         if def_names.contains(nam) {
           *self = Term::r#ref(nam);
+        } else if let Some(proj) = tuple_projection(nam, def_names, scope) {
+          *self = proj;
         }
       }
 
@@ -76,6 +78,38 @@ impl Term {
   }
 }
 
+/// If the name is a projection of a tuple, like `x.0` or `x.1`, returns its desugaring into a tuple destructuring.
+///
+/// Projections can be chained, so `x.1.0` is the first element of the second element of `x`.
+/// On their own, `.0` and `.1` are the functions that return the first and second elements of a pair.
+fn tuple_projection(nam: &Name, def_names: &HashSet<Name>, scope: &HashMap<&Name, usize>) -> Option<Term> {
+  let (tup, idx) = nam.rsplit_once('.')?;
+  let idx = match idx {
+    "0" => 0,
+    "1" => 1,
+    _ => return None,
+  };
+  let tup = Name::new(tup);
+  let tup_is_bound = scope.get(&tup).is_some_and(|n| *n > 0) || def_names.contains(&tup);
+  if !tup.is_empty() && !tup_is_bound && tuple_projection(&tup, def_names, scope).is_none() {
+    return None;
+  }
+
+  let el = Name::new("%proj");
+  let els = (0 .. 2).map(|i| Pattern::Var((i == idx).then(|| el.clone()))).collect();
+  let proj = |tup: Term| Term::Let {
+    pat: Box::new(Pattern::Fan(FanKind::Tup, Tag::Static, els)),
+    val: Box::new(tup),
+    nxt: Box::new(Term::Var { nam: el.clone() }),
+  };
+  if tup.is_empty() {
+    let arg = Name::new("%tup");
+    Some(Term::lam(Pattern::Var(Some(arg.clone())), proj(Term::Var { nam: arg })))
+  } else {
+    Some(proj(Term::Var { nam: tup }))
+  }
+}
+
 fn push_scope<'a>(name: Option<&'a Name>, scope: &mut HashMap<&'a Name, usize>) {
   if let Some(name) = name {
     let var_scope = scope.entry(name).or_default();
@@ -0,0 +1,186 @@
+use crate::{
+  fun::{Book, Definition, Name, Num, Op, Pattern, Rule, Term},
+  maybe_grow,
+};
+
+impl Book {
+  /// Turns accumulator-style recursion over native numbers into tail recursion.
+  ///
+  /// Only definitions that `switch` on a number, return an integer literal on the zero case
+  /// and combine the recursive call with an associative and commutative operation are changed.
+  ///
+  /// Example:
+  /// ```bend
+  /// sum n = switch n {
+  ///   0: 0
+  ///   _: (+ n (sum n-1))
+  /// }
+  ///
+  /// // Transforms to:
+  /// sum n = switch n {
+  ///   0: 0
+  ///   _: (sum__tail n-1 n)
+  /// }
+  ///
+  /// sum__tail n acc = switch n {
+  ///   0: (+ acc 0)
+  ///   _: (sum__tail n-1 (+ acc n))
+  /// }
+  /// ```
+  /// Since the recursive call is no longer an operand, the pending operations don't pile up in the net.
+  ///
+  /// If there's already a definition called `sum__tail`, a number is added to the name until it's unique.
+  pub fn tail_recursion(&mut self) {
+    // The new definitions are added at the end, so they're not visited by this loop.
+    for def_idx in 0 .. self.defs.len() {
+      let def_name = &self.defs[def_idx].name;
+      let mut tail_nam = Name::new(format!("{def_name}__tail"));
+      let mut idx = 0;
+      while self.defs.contains_key(&tail_nam) || self.hvm_defs.contains_key(&tail_nam) {
+        tail_nam = Name::new(format!("{def_name}__tail{idx}"));
+        idx += 1;
+      }
+
+      if let Some(new_def) = self.defs[def_idx].tail_recursion(tail_nam) {
+        self.defs.insert(new_def.name.clone(), new_def);
+      }
+    }
+  }
+}
+
+impl Definition {
+  /// If this definition can be made tail recursive, changes it to call the returned tail recursive version,
+  /// which is called `tail_nam`.
+  fn tail_recursion(&mut self, tail_nam: Name) -> Option<Definition> {
+    let [rule] = self.rules.as_mut_slice() else { return None };
+
+    // The definition is expected to be in the shape generated by `desugar_match_defs`.
+    let (arity, swt) = rule.body.skip_arg_lams();
+    let Term::Swt { with, arms, .. } = swt.skip_uses() else { return None };
+    let [base, step] = arms.as_slice() else { return None };
+    let (base, Term::Oper { opr, fst, snd }) = (base.skip_uses(), step.skip_uses()) else { return None };
+    if arity == 0
+      || !with.is_empty()
+      || !opr.is_associative()
+      || !matches!(base, Term::Num { val: Num::U24(_) | Num::I24(_) })
+    {
+      return None;
+    }
+
+    // The recursive call must be one of the operands, and can't appear anywhere else.
+    let (call, other) = match (fst.is_call_to(&self.name, arity), snd.is_call_to(&self.name, arity)) {
+      (true, false) => (fst.as_ref().clone(), snd.as_ref().clone()),
+      (false, true) => (snd.as_ref().clone(), fst.as_ref().clone()),
+      _ => return None,
+    };
+    if other.has_ref_to(&self.name) || call.has_nested_ref_to(&self.name) {
+      return None;
+    }
+    let opr = *opr;
+
+    let acc = Name::new("%acc");
+    let tail_call = |acc: Term| {
+      let mut call = call.clone();
+      call.replace_called_ref(&tail_nam);
+      Term::app(call, acc)
+    };
+
+    // The tail recursive version accumulates the operands, combining them with the base case at the end.
+    let mut tail_body = rule.body.clone();
+    let Term::Swt { arms, .. } = tail_body.skip_arg_lams_mut().skip_uses_mut() else { unreachable!() };
+    let acc_var = Term::Var { nam: acc.clone() };
+    let base = arms[0].skip_uses_mut();
+    *base = Term::Oper { opr, fst: Box::new(acc_var.clone()), snd: Box::new(std::mem::take(base)) };
+    *arms[1].skip_uses_mut() =
+      tail_call(Term::Oper { opr, fst: Box::new(acc_var), snd: Box::new(other.clone()) });
+    let swt = tail_body.skip_arg_lams_mut();
+    *swt = Term::lam(Pattern::Var(Some(acc)), std::mem::take(swt));
+    let tail_def = Definition {
+      name: tail_nam.clone(),
+      rules: vec![Rule { pats: vec![], body: tail_body }],
+      builtin: self.builtin,
+      io: self.io,
+    };
+
+    // The original definition starts the accumulator with the first operand.
+    let Term::Swt { arms, .. } = rule.body.skip_arg_lams_mut().skip_uses_mut() else { unreachable!() };
+    *arms[1].skip_uses_mut() = tail_call(other);
+
+    Some(tail_def)
+  }
+}
+
+impl Term {
+  /// Returns the number of argument lambdas at the start of the term and what comes after them.
+  fn skip_arg_lams(&self) -> (usize, &Term) {
+    let mut arity = 0;
+    let mut term = self;
+    while let Term::Lam { pat: box Pattern::Var(Some(_)), bod, .. } = term {
+      arity += 1;
+      term = bod;
+    }
+    (arity, term)
+  }
+
+  fn skip_arg_lams_mut(&mut self) -> &mut Term {
+    match self {
+      Term::Lam { pat: box Pattern::Var(Some(_)), bod, .. } => bod.skip_arg_lams_mut(),
+      _ => self,
+    }
+  }
+
+  fn skip_uses(&self) -> &Term {
+    match self {
+      Term::Use { nxt, .. } => nxt.skip_uses(),
+      _ => self,
+    }
+  }
+
+  fn skip_uses_mut(&mut self) -> &mut Term {
+    match self {
+      Term::Use { nxt, .. } => nxt.skip_uses_mut(),
+      _ => self,
+    }
+  }
+
+  /// Whether this term is a call to `nam` with exactly `arity` arguments.
+  fn is_call_to(&self, nam: &Name, arity: usize) -> bool {
+    let mut called = self;
+    for _ in 0 .. arity {
+      let Term::App { fun, .. } = called else { return false };
+      called = fun;
+    }
+    matches!(called, Term::Ref { nam: ref_nam } if ref_nam == nam)
+  }
+
+  /// Whether any of the arguments of this call references `nam`.
+  fn has_nested_ref_to(&self, nam: &Name) -> bool {
+    match self {
+      Term::App { fun, arg, .. } => arg.has_ref_to(nam) || fun.has_nested_ref_to(nam),
+      _ => false,
+    }
+  }
+
+  fn has_ref_to(&self, nam: &Name) -> bool {
+    maybe_grow(|| match self {
+      Term::Ref { nam: ref_nam } => ref_nam == nam,
+      _ => self.children().any(|child| child.has_ref_to(nam)),
+    })
+  }
+
+  /// Replaces the function called by this call term.
+  fn replace_called_ref(&mut self, new_nam: &Name) {
+    match self {
+      Term::App { fun, .. } => fun.replace_called_ref(new_nam),
+      Term::Ref { nam } => *nam = new_nam.clone(),
+      _ => unreachable!(),
+    }
+  }
+}
+
+impl Op {
+  /// Whether the operands can be regrouped and reordered without changing the result for integers.
+  fn is_associative(&self) -> bool {
+    matches!(self, Op::ADD | Op::MUL | Op::AND | Op::OR | Op::XOR)
+  }
+}
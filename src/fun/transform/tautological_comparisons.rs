@@ -0,0 +1,125 @@
+use crate::{
+  fun::{Book, Name, Num, Op, Pattern, Term},
+  maybe_grow,
+};
+
+impl Book {
+  /// Replaces the comparisons of a term with itself by their result, like `(== x x)` by `1` and `(< x x)` by `0`.
+  ///
+  /// The operands only need to be the same up to the names of their bound variables, see [`Term::alpha_eq`].
+  /// Operands with scopeless variables are never folded, since each occurrence binds or uses a different value.
+  ///
+  /// Comparisons whose operands can be floats are kept, since a NaN isn't equal to itself.
+  pub fn fold_tautological_comparisons(&mut self) {
+    for def in self.defs.values_mut() {
+      for rule in def.rules.iter_mut() {
+        rule.body.fold_tautological_comparisons();
+      }
+    }
+  }
+}
+
+impl Term {
+  pub fn fold_tautological_comparisons(&mut self) {
+    maybe_grow(|| {
+      for child in self.children_mut() {
+        child.fold_tautological_comparisons();
+      }
+    });
+
+    if let Term::Oper { opr, fst, snd } = self {
+      let res = match opr {
+        Op::EQ => 1,
+        Op::NEQ | Op::LT | Op::GT => 0,
+        _ => return,
+      };
+      if !fst.may_be_float() && fst.alpha_eq(snd) {
+        *self = Term::Num { val: Num::U24(res) };
+      }
+    }
+  }
+
+  /// Whether the term can be a float, because it has a float number or an operation that returns one.
+  fn may_be_float(&self) -> bool {
+    maybe_grow(|| match self {
+      Term::Num { val: Num::F24(_) } | Term::Oper { opr: Op::ATN | Op::LOG | Op::POW, .. } => true,
+      _ => self.children().any(Term::may_be_float),
+    })
+  }
+
+  /// Whether two terms are the same, except for the names of the variables they bind.
+  ///
+  /// Only lambdas, lets, applications, tuples, superpositions, numeric operations and the terms without children
+  /// are compared, any other term is considered different from everything, as are scopeless variables.
+  pub fn alpha_eq(&self, other: &Term) -> bool {
+    alpha_eq(self, other, &mut vec![])
+  }
+}
+
+/// Compares two terms, with `binds` holding the pairs of variables bound by both, innermost last.
+fn alpha_eq<'a>(a: &'a Term, b: &'a Term, binds: &mut Vec<(Option<&'a Name>, Option<&'a Name>)>) -> bool {
+  maybe_grow(|| match (a, b) {
+    (Term::Var { nam: a }, Term::Var { nam: b }) => {
+      let a_idx = binds.iter().rposition(|(bind, _)| *bind == Some(a));
+      let b_idx = binds.iter().rposition(|(_, bind)| *bind == Some(b));
+      match (a_idx, b_idx) {
+        (Some(a_idx), Some(b_idx)) => a_idx == b_idx,
+        (None, None) => a == b,
+        _ => false,
+      }
+    }
+    (Term::Num { val: a }, Term::Num { val: b }) => a == b,
+    (Term::Ref { nam: a }, Term::Ref { nam: b }) => a == b,
+    (Term::Era, Term::Era) => true,
+    (Term::Lam { tag: a_tag, pat: a_pat, bod: a_bod }, Term::Lam { tag: b_tag, pat: b_pat, bod: b_bod }) => {
+      let len = binds.len();
+      let eq = a_tag == b_tag && pattern_binds(a_pat, b_pat, binds) && alpha_eq(a_bod, b_bod, binds);
+      binds.truncate(len);
+      eq
+    }
+    (Term::Let { pat: a_pat, val: a_val, nxt: a_nxt }, Term::Let { pat: b_pat, val: b_val, nxt: b_nxt }) => {
+      if !alpha_eq(a_val, b_val, binds) {
+        return false;
+      }
+      let len = binds.len();
+      let eq = pattern_binds(a_pat, b_pat, binds) && alpha_eq(a_nxt, b_nxt, binds);
+      binds.truncate(len);
+      eq
+    }
+    (Term::App { tag: a_tag, fun: a_fun, arg: a_arg }, Term::App { tag: b_tag, fun: b_fun, arg: b_arg }) => {
+      a_tag == b_tag && alpha_eq(a_fun, b_fun, binds) && alpha_eq(a_arg, b_arg, binds)
+    }
+    (Term::Fan { fan: a_fan, tag: a_tag, els: a_els }, Term::Fan { fan: b_fan, tag: b_tag, els: b_els }) => {
+      a_fan == b_fan
+        && a_tag == b_tag
+        && a_els.len() == b_els.len()
+        && a_els.iter().zip(b_els).all(|(a, b)| alpha_eq(a, b, binds))
+    }
+    (
+      Term::Oper { opr: a_opr, fst: a_fst, snd: a_snd },
+      Term::Oper { opr: b_opr, fst: b_fst, snd: b_snd },
+    ) => a_opr == b_opr && alpha_eq(a_fst, b_fst, binds) && alpha_eq(a_snd, b_snd, binds),
+    _ => false,
+  })
+}
+
+/// Pairs the variables bound by two patterns of the same shape, returning whether they have the same shape.
+fn pattern_binds<'a>(
+  a: &'a Pattern,
+  b: &'a Pattern,
+  binds: &mut Vec<(Option<&'a Name>, Option<&'a Name>)>,
+) -> bool {
+  maybe_grow(|| match (a, b) {
+    (Pattern::Var(a), Pattern::Var(b)) => {
+      binds.push((a.as_ref(), b.as_ref()));
+      true
+    }
+    (Pattern::Fan(a_fan, a_tag, a_els), Pattern::Fan(b_fan, b_tag, b_els)) => {
+      a_fan == b_fan
+        && a_tag == b_tag
+        && a_els.len() == b_els.len()
+        && a_els.iter().zip(b_els).all(|(a, b)| pattern_binds(a, b, binds))
+    }
+    _ => false,
+  })
+}
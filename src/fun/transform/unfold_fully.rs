@@ -0,0 +1,149 @@
+use crate::{
+  fun::{Book, Name, Term},
+  maybe_grow,
+};
+use indexmap::IndexSet;
+
+/// Limits on how much [`Book::unfold_with_budget`] can unfold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InlineBudget {
+  /// How many levels of references are unfolded.
+  /// With `1` only the definition itself is inlined, keeping the references in its body,
+  /// and each extra level also unfolds the helpers one level deeper.
+  pub max_inline_depth: usize,
+  /// How many term nodes the rules of the book can add up to after unfolding.
+  pub max_total_nodes: usize,
+}
+
+impl Default for InlineBudget {
+  /// No limits, unfolding everything that can be unfolded.
+  fn default() -> Self {
+    Self { max_inline_depth: usize::MAX, max_total_nodes: usize::MAX }
+  }
+}
+
+impl Book {
+  /// Replaces every reference to the definition `def_name` with its body.
+  ///
+  /// The inlined body has the non-recursive definitions it references unfolded as well,
+  /// so that after a chain of helpers is unfolded, the users no longer depend on any of them.
+  /// References to recursive helpers are kept as they are.
+  ///
+  /// Since definition bodies are closed terms, they can be inlined without renaming
+  /// their variables. Name clashes are later resolved by `make_var_names_unique`.
+  ///
+  /// Precondition: References have been resolved and match definitions desugared.
+  ///
+  /// Example:
+  /// ```bend
+  /// inc = λx (+ x 1)
+  /// inc2 = λx (inc (inc x))
+  /// main = (inc2 0)
+  ///
+  /// // Unfolding `inc2` transforms `main` to:
+  /// main = (λx (λx (+ x 1) (λx (+ x 1) x)) 0)
+  /// ```
+  pub fn unfold_fully(&mut self, def_name: &Name) -> Result<(), String> {
+    self.unfold_with_budget(def_name, InlineBudget::default()).map(|_| ())
+  }
+
+  /// Like [`Book::unfold_fully`], but only unfolds as much as the budget allows.
+  ///
+  /// Once replacing one more reference would make the book larger than the budget,
+  /// the remaining references are left as they are.
+  /// Returns how many references to `def_name` were replaced.
+  pub fn unfold_with_budget(&mut self, def_name: &Name, budget: InlineBudget) -> Result<usize, String> {
+    if !self.defs.contains_key(def_name) {
+      return Err(format!("Definition '{def_name}' not found."));
+    }
+    let recursive = self.recursive_defs();
+    if recursive.contains(def_name) {
+      return Err(format!("Can't unfold '{def_name}', since it's a recursive definition."));
+    }
+
+    if budget.max_inline_depth == 0 {
+      return Ok(0);
+    }
+
+    let mut bodies = vec![];
+    let body = self.unfolded_body(def_name, budget.max_inline_depth - 1, &recursive, &mut bodies)?;
+    // A definition with unscoped variables can't be copied, since each unscoped variable can only be bound once.
+    if body.has_unscoped() {
+      return Err(format!("Can't unfold '{def_name}', since it uses unscoped variables."));
+    }
+
+    // Each replacement swaps a reference node for the nodes of the body.
+    let total_nodes = self.defs.values().flat_map(|def| &def.rules).map(|rule| rule.body.node_count()).sum();
+    let added_nodes = body.node_count() - 1;
+    let allowed = match budget.max_total_nodes.checked_sub(total_nodes) {
+      Some(_) if added_nodes == 0 => usize::MAX,
+      Some(free_nodes) => free_nodes / added_nodes,
+      None => 0,
+    };
+
+    let mut remaining = allowed;
+    for (nam, def) in self.defs.iter_mut() {
+      if nam != def_name {
+        for rule in def.rules.iter_mut() {
+          rule.body.unfold_ref(def_name, &body, &mut remaining);
+        }
+      }
+    }
+    Ok(allowed - remaining)
+  }
+
+  /// Returns the body of a definition with the non-recursive definitions it references unfolded,
+  /// up to `depth` levels of references.
+  ///
+  /// `recursive` are the recursive definitions of the book, which are never unfolded,
+  /// and `unfolded` caches the bodies of the definitions that were already unfolded.
+  fn unfolded_body(
+    &self,
+    def_name: &Name,
+    depth: usize,
+    recursive: &IndexSet<Name>,
+    unfolded: &mut Vec<(Name, usize, Term)>,
+  ) -> Result<Term, String> {
+    if let Some((_, _, body)) = unfolded.iter().find(|(nam, d, _)| nam == def_name && *d == depth) {
+      return Ok(body.clone());
+    }
+    let rule = match self.defs[def_name].rules.as_slice() {
+      [rule] if rule.pats.is_empty() => rule,
+      _ => {
+        return Err(format!(
+          "Can't unfold '{def_name}', since its pattern matching rules weren't desugared."
+        ));
+      }
+    };
+
+    let mut body = rule.body.clone();
+    if depth > 0 {
+      let mut unlimited = usize::MAX;
+      for nam in rule.body.collect_refs() {
+        if self.defs.get(&nam).is_some_and(|def| !def.builtin) && !recursive.contains(&nam) {
+          let ref_body = self.unfolded_body(&nam, depth - 1, recursive, unfolded)?;
+          body.unfold_ref(&nam, &ref_body, &mut unlimited);
+        }
+      }
+    }
+    unfolded.push((def_name.clone(), depth, body.clone()));
+    Ok(body)
+  }
+}
+
+impl Term {
+  /// Replaces the references to `def_name` with the given body,
+  /// up to `remaining` of them, decrementing it for each one.
+  fn unfold_ref(&mut self, def_name: &Name, body: &Term, remaining: &mut usize) {
+    self.inline_refs(&mut |nam| {
+      (nam == def_name && *remaining > 0).then(|| {
+        *remaining -= 1;
+        body.clone()
+      })
+    });
+  }
+
+  fn node_count(&self) -> usize {
+    maybe_grow(|| 1 + self.children().map(Term::node_count).sum::<usize>())
+  }
+}
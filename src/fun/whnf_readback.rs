@@ -0,0 +1,70 @@
+use crate::fun::{builtins, Name, Num, Pattern, Term};
+
+impl Term {
+  /// Keeps only the weak head normal form of a readback result,
+  /// replacing everything under its head with `*`.
+  ///
+  /// The head is the outermost constructor, superposition or lambda.
+  /// Fields of the head that are atoms, like numbers or references, are kept,
+  /// since there's nothing under them to hide.
+  ///
+  /// Example:
+  /// ```bend
+  /// [1, 2, 3]
+  ///
+  /// // Transforms to:
+  /// (List/Cons 1 *)
+  /// ```
+  pub fn hide_below_head(&mut self) {
+    match self {
+      Term::List { els } if !els.is_empty() => {
+        let head = std::mem::take(&mut els[0]);
+        *self = Term::call(Term::r#ref(builtins::LCONS), [head, Term::Era]);
+      }
+      Term::Str { val } if !val.is_empty() => {
+        let head = Term::Num { val: Num::U24(val.chars().next().unwrap() as u32) };
+        *self = Term::call(Term::r#ref(builtins::SCONS), [head, Term::Era]);
+      }
+      // Scott-encoded constructors: `λx (x fields..)`
+      Term::Lam { pat: box Pattern::Var(Some(var)), bod, .. } if bod.called_var() == Some(&*var) => {
+        bod.truncate_args();
+        return;
+      }
+      Term::Lam { bod, .. } => {
+        **bod = Term::Era;
+        return;
+      }
+      Term::Fan { els, .. } => {
+        els.iter_mut().for_each(Term::truncate_to_atom);
+        return;
+      }
+      _ => {}
+    }
+    self.truncate_args();
+  }
+
+  /// Replaces the non-atomic arguments of a call with `*`.
+  fn truncate_args(&mut self) {
+    let mut called = self;
+    while let Term::App { fun, arg, .. } = called {
+      arg.truncate_to_atom();
+      called = fun;
+    }
+  }
+
+  fn truncate_to_atom(&mut self) {
+    if !matches!(self, Term::Num { .. } | Term::Var { .. } | Term::Link { .. } | Term::Ref { .. } | Term::Era)
+    {
+      *self = Term::Era;
+    }
+  }
+
+  /// If this term is an application of a variable, returns the variable.
+  fn called_var(&self) -> Option<&Name> {
+    match self {
+      Term::App { fun, .. } => fun.called_var(),
+      Term::Var { nam } => Some(nam),
+      _ => None,
+    }
+  }
+}
@@ -0,0 +1,64 @@
+use super::display_hvm_def;
+use crate::fun::{display::DisplayFn, transform::definition_merge::MERGE_SEPARATOR, Name};
+use itertools::Itertools;
+use std::collections::BTreeMap;
+
+/// Maps the nets of a compiled book back to the definitions of the program they were generated from.
+///
+/// The rules of a definition are merged into a single term when its pattern matching is compiled,
+/// so the origin of a net is tracked only up to the definition.
+/// A net can come from more than one definition if equal definitions were merged.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DebugSymbols {
+  origins: BTreeMap<String, Vec<Name>>,
+}
+
+impl DebugSymbols {
+  /// Finds the origin of each net of `hvm_book` among the definitions of the program.
+  ///
+  /// The definitions created during compilation are named by appending a `__` suffix to the name
+  /// of the one they were extracted from, so each net is attributed to the longest definition name that is
+  /// a prefix of its own.
+  pub fn new(src_defs: &[Name], hvm_book: &hvm::ast::Book) -> Self {
+    let mut src_defs = src_defs.to_vec();
+    src_defs.sort_by_key(|nam| std::cmp::Reverse(nam.len()));
+
+    let mut origins = BTreeMap::new();
+    for net_nam in hvm_book.defs.keys() {
+      let origin = net_nam
+        .split(MERGE_SEPARATOR)
+        .filter_map(|part| {
+          src_defs.iter().find(|src| {
+            part.strip_prefix(src.as_ref()).is_some_and(|rest| rest.is_empty() || rest.starts_with("__"))
+          })
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+      origins.insert(net_nam.clone(), origin);
+    }
+    Self { origins }
+  }
+
+  /// The definitions that the net with the given name was generated from.
+  pub fn origin(&self, net_nam: &str) -> &[Name] {
+    self.origins.get(net_nam).map_or(&[], Vec::as_slice)
+  }
+}
+
+/// Displays a book like [`super::display_hvm_book`],
+/// with a comment before each net naming the definitions it was generated from.
+pub fn display_hvm_book_with_symbols<'a>(
+  book: &'a hvm::ast::Book,
+  symbols: &'a DebugSymbols,
+) -> impl std::fmt::Display + 'a {
+  DisplayFn(move |f| {
+    for (nam, def) in book.defs.iter() {
+      let origin = symbols.origin(nam);
+      if !origin.is_empty() {
+        writeln!(f, "// from: {}", origin.iter().join(", "))?;
+      }
+      writeln!(f, "{}", display_hvm_def(nam, def))?;
+    }
+    Ok(())
+  })
+}
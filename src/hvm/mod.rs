@@ -3,6 +3,7 @@ use hvm::ast::{Net, Tree};
 
 pub mod add_recursive_priority;
 pub mod check_net_size;
+pub mod debug_symbols;
 pub mod eta_reduce;
 pub mod inline;
 pub mod mutual_recursion;
@@ -36,14 +37,33 @@ pub fn net_trees_mut(net: &mut Net) -> impl DoubleEndedIterator<Item = &mut Tree
   [&mut net.root].into_iter().chain(net.rbag.iter_mut().flat_map(|(_, fst, snd)| [fst, snd]))
 }
 
+/// The names of the definitions referenced by a net.
+pub fn net_refs(net: &Net) -> Vec<&str> {
+  let mut refs = vec![];
+  let mut to_visit = net_trees(net).collect::<Vec<_>>();
+  while let Some(tree) = to_visit.pop() {
+    if let Tree::Ref { nam } = tree {
+      refs.push(nam.as_str());
+    }
+    to_visit.extend(tree_children(tree));
+  }
+  refs
+}
+
 pub fn display_hvm_book(book: &hvm::ast::Book) -> impl std::fmt::Display + '_ {
   DisplayFn(|f| {
     for (nam, def) in book.defs.iter() {
-      writeln!(f, "@{} = {}", nam, display_hvm_tree(&def.root))?;
-      for (pri, a, b) in def.rbag.iter() {
-        writeln!(f, "  &{}{} ~ {}", if *pri { "!" } else { " " }, display_hvm_tree(a), display_hvm_tree(b))?;
-      }
-      writeln!(f)?;
+      writeln!(f, "{}", display_hvm_def(nam, def))?;
+    }
+    Ok(())
+  })
+}
+
+pub fn display_hvm_def<'a>(nam: &'a str, def: &'a Net) -> impl std::fmt::Display + 'a {
+  DisplayFn(move |f| {
+    writeln!(f, "@{} = {}", nam, display_hvm_tree(&def.root))?;
+    for (pri, a, b) in def.rbag.iter() {
+      writeln!(f, "  &{}{} ~ {}", if *pri { "!" } else { " " }, display_hvm_tree(a), display_hvm_tree(b))?;
     }
     Ok(())
   })
@@ -96,7 +116,10 @@ pub fn display_hvm_numb(numb: &hvm::ast::Numb) -> impl std::fmt::Display + '_ {
     }
     _ => {
       let typ = numb.get_typ();
-      let val = numb.get_u24();
+      // The operand of a partial application doesn't keep its type, but a negative
+      // i24 leaves the sign extended into the upper bits, so we can still show it signed.
+      let val =
+        if numb.0 >> 29 == 0b111 { format!("{:+}", numb.get_i24()) } else { numb.get_u24().to_string() };
       format!(
         "[{}{}]",
         match typ {
@@ -602,7 +602,7 @@ impl<'a> PyParser<'a> {
   fn parse_match_case(&mut self, indent: &mut Indent) -> ParseResult<(MatchArm, Indent)> {
     self.parse_keyword("case")?;
     self.skip_trivia_inline();
-    let pat = if self.try_consume_exactly("_") {
+    let pat = if self.try_consume_exactly("_") || self.try_parse_keyword("otherwise") {
       None
     } else {
       let nam = self.labelled(|p| p.parse_bend_name(), "name or '_'")?;
@@ -676,6 +676,7 @@ impl<'a> PyParser<'a> {
           None
         }
         c if c.is_ascii_digit() => Some(self.parse_u32()?),
+        _ if self.try_parse_keyword("otherwise") => None,
         _ => return self.expected("number or '_'"),
       }
     } else {
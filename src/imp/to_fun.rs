@@ -18,7 +18,7 @@ impl Definition {
     let rule =
       fun::Rule { pats: self.params.into_iter().map(|param| fun::Pattern::Var(Some(param))).collect(), body };
 
-    let def = fun::Definition { name: self.name, rules: vec![rule], builtin };
+    let def = fun::Definition { name: self.name, rules: vec![rule], builtin, io: false };
     Ok(def)
   }
 }
@@ -2,19 +2,22 @@
 #![feature(let_chains)]
 
 use crate::{
-  fun::{book_to_hvm, net_to_term::net_to_term, term_to_net::Labels, Book, Ctx, Term},
+  fun::{book_to_hvm, net_to_term::net_to_term, term_to_net::Labels, Book, Ctx, Name, Term},
   hvm::{
     add_recursive_priority::add_recursive_priority,
     check_net_size::{check_net_sizes, MAX_NET_SIZE},
-    display_hvm_book,
+    debug_symbols::DebugSymbols,
+    display_hvm_book, display_hvm_def,
     eta_reduce::eta_reduce_hvm_net,
     inline::inline_hvm_book,
     mutual_recursion,
     prune::prune_hvm_book,
   },
 };
-use diagnostics::{Diagnostics, DiagnosticsConfig, ERR_INDENT_SIZE};
+use diagnostics::{Diagnostic, DiagnosticOrigin, Diagnostics, DiagnosticsConfig, ERR_INDENT_SIZE};
+use itertools::Itertools;
 use net::hvm_to_net::hvm_to_net;
+use std::path::Path;
 
 pub mod diagnostics;
 pub mod fun;
@@ -39,12 +42,38 @@ pub fn check_book(
   Ok(res.diagnostics)
 }
 
+/// Compiles a copy of the book all the way to HVM nets, discarding them,
+/// and returns every diagnostic found along the way.
+///
+/// Unlike [`desugar_book`], this also finds the problems that only show up while lowering to nets,
+/// like recursion cycles or nets that are too large, so it's meant for tools that
+/// want to report all the problems of a program without producing any output.
+pub fn compile_check(
+  book: &Book,
+  compile_opts: CompileOpts,
+  diagnostics_cfg: DiagnosticsConfig,
+) -> Vec<(DiagnosticOrigin, Diagnostic)> {
+  let diagnostics = match compile_book(&mut book.clone(), compile_opts, diagnostics_cfg, None) {
+    Ok(res) => res.diagnostics,
+    Err(diagnostics) => diagnostics,
+  };
+  diagnostics
+    .diagnostics
+    .into_iter()
+    .flat_map(|(orig, diags)| diags.into_iter().map(move |diag| (orig.clone(), diag)))
+    .collect()
+}
+
 pub fn compile_book(
   book: &mut Book,
   opts: CompileOpts,
   diagnostics_cfg: DiagnosticsConfig,
   args: Option<Vec<Term>>,
 ) -> Result<CompileResult, Diagnostics> {
+  let src_defs = opts.debug_symbols.then(|| {
+    book.defs.keys().chain(book.hvm_defs.keys()).chain(book.ctrs.keys()).cloned().collect::<Vec<_>>()
+  });
+
   let mut diagnostics = desugar_book(book, opts.clone(), diagnostics_cfg, args)?;
 
   let (mut hvm_book, labels) = book_to_hvm(book, &mut diagnostics)?;
@@ -78,7 +107,124 @@ pub fn compile_book(
 
   add_recursive_priority(&mut hvm_book);
 
-  Ok(CompileResult { hvm_book, labels, diagnostics })
+  let debug_symbols = src_defs.map(|src_defs| DebugSymbols::new(&src_defs, &hvm_book));
+
+  Ok(CompileResult { hvm_book, labels, diagnostics, debug_symbols })
+}
+
+/// Compiles the book and writes the net of each definition to its own `<dir>/<name>.hvm` file.
+pub fn compile_to_files(
+  book: &mut Book,
+  opts: CompileOpts,
+  diagnostics_cfg: DiagnosticsConfig,
+  dir: &Path,
+) -> Result<Diagnostics, Diagnostics> {
+  let res = compile_book(book, opts, diagnostics_cfg, None)?;
+
+  std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create '{}'. {e}", dir.display()))?;
+  for (nam, def) in res.hvm_book.defs.iter() {
+    let path = dir.join(format!("{}.hvm", def_file_name(nam)));
+    std::fs::write(&path, display_hvm_def(nam, def).to_string())
+      .map_err(|e| format!("Failed to write '{}'. {e}", path.display()))?;
+  }
+
+  Ok(res.diagnostics)
+}
+
+/// Compiles a source file, reusing the compiled book cached in `cache_dir` if the same
+/// source was already compiled with the same options.
+///
+/// The cache is keyed by a hash of the source code, the compile and diagnostics options and the compiler version,
+/// storing each compiled book as text in `<cache_dir>/<hash>.hvm`, with its labels in comments before it.
+/// Since the pipeline doesn't run on a cache hit, warnings are only reported on the first compilation.
+pub fn compile_file_cached(
+  code: &str,
+  path: &Path,
+  opts: CompileOpts,
+  diagnostics_cfg: DiagnosticsConfig,
+  cache_dir: &Path,
+) -> Result<CachedCompileResult, Diagnostics> {
+  let cache_file = cache_dir.join(format!("{:016x}.hvm", cache_key(code, &opts, &diagnostics_cfg)));
+
+  // A cache file that can't be read is treated as a miss and overwritten.
+  if let Ok(cached) = std::fs::read_to_string(&cache_file)
+    && let Ok(hvm_book) = ::hvm::ast::CoreParser::new(&cached).parse_book()
+    && let Some(labels) = parse_cached_labels(&cached)
+  {
+    let diagnostics = Diagnostics::new(diagnostics_cfg);
+    return Ok(CachedCompileResult { hvm_book, labels, diagnostics, cache_hit: true });
+  }
+
+  let mut book = fun::load_book::do_parse_book(code, path, Book::builtins())?;
+  let res = compile_book(&mut book, opts, diagnostics_cfg, None)?;
+
+  std::fs::create_dir_all(cache_dir)
+    .map_err(|e| format!("Failed to create '{}'. {e}", cache_dir.display()))?;
+  let cached = format!("{}{}", display_cached_labels(&res.labels), display_hvm_book(&res.hvm_book));
+  std::fs::write(&cache_file, cached)
+    .map_err(|e| format!("Failed to write '{}'. {e}", cache_file.display()))?;
+
+  let CompileResult { hvm_book, labels, diagnostics, .. } = res;
+  Ok(CachedCompileResult { hvm_book, labels, diagnostics, cache_hit: false })
+}
+
+fn cache_key(code: &str, opts: &CompileOpts, diagnostics_cfg: &DiagnosticsConfig) -> u64 {
+  use std::hash::{DefaultHasher, Hash, Hasher};
+  let mut hasher = DefaultHasher::new();
+  env!("CARGO_PKG_VERSION").hash(&mut hasher);
+  format!("{opts:?}").hash(&mut hasher);
+  format!("{diagnostics_cfg:?}").hash(&mut hasher);
+  code.hash(&mut hasher);
+  hasher.finish()
+}
+
+const CACHED_LABEL: &str = "// label ";
+
+/// Writes the names given to labels as `// label <kind> <label> <name>` comments, which hvm skips when parsing.
+fn display_cached_labels(labels: &Labels) -> String {
+  let mut out = String::new();
+  for (kind, generator) in [("con", &labels.con), ("dup", &labels.dup), ("tup", &labels.tup)] {
+    for (label, nam) in generator.label_to_name.iter().sorted() {
+      out.push_str(&format!("{CACHED_LABEL}{kind} {label} {nam}\n"));
+    }
+  }
+  out
+}
+
+/// Reads back the labels written by [display_cached_labels], returning `None` if a label comment is malformed.
+fn parse_cached_labels(cached: &str) -> Option<Labels> {
+  let mut labels = Labels::default();
+  for line in cached.lines().filter_map(|line| line.strip_prefix(CACHED_LABEL)) {
+    let (kind, rest) = line.split_once(' ')?;
+    let (label, nam) = rest.split_once(' ')?;
+    let generator = match kind {
+      "con" => &mut labels.con,
+      "dup" => &mut labels.dup,
+      "tup" => &mut labels.tup,
+      _ => return None,
+    };
+    generator.label_to_name.insert(label.parse().ok()?, Name::new(nam));
+  }
+  // The labels of a compiled book can't be used to generate new ones.
+  for generator in [&mut labels.con, &mut labels.dup, &mut labels.tup] {
+    generator.next = u16::MAX;
+  }
+  Some(labels)
+}
+
+/// Escapes the characters of a definition name that can't be safely used in a file name.
+///
+/// Keeps alphanumeric characters, `_`, `-` and any `.` that's not at the start, escaping the rest as `%XX`.
+fn def_file_name(nam: &str) -> String {
+  let mut file_name = String::new();
+  for (i, byte) in nam.bytes().enumerate() {
+    if byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'-' || (byte == b'.' && i != 0) {
+      file_name.push(byte as char);
+    } else {
+      file_name.push_str(&format!("%{byte:02X}"));
+    }
+  }
+  file_name
 }
 
 pub fn desugar_book(
@@ -99,14 +245,32 @@ pub fn desugar_book(
 
   ctx.apply_args(args)?;
 
+  ctx.desugar_ctr_fields()?;
+
   ctx.desugar_open()?;
 
   ctx.book.encode_builtins();
 
   ctx.resolve_refs()?;
 
+  ctx.check_io_annotations()?;
+
+  ctx.check_unconstructed_ctrs()?;
+
+  ctx.check_self_applications()?;
+
+  ctx.check_unsigned_underflow()?;
+
   ctx.desugar_match_defs()?;
 
+  if opts.if_chains {
+    ctx.book.convert_if_chains();
+  }
+
+  if opts.comparisons_to_bool {
+    ctx.book.comparisons_to_bool();
+  }
+
   ctx.fix_match_terms()?;
 
   ctx.desugar_bend()?;
@@ -115,6 +279,23 @@ pub fn desugar_book(
 
   ctx.check_unbound_vars()?;
 
+  if opts.annihilate_dup_sups {
+    ctx.book.annihilate_dup_sups();
+  }
+
+  if opts.tail_recursion {
+    ctx.book.tail_recursion();
+  }
+
+  if opts.inline_constants {
+    ctx.book.inline_constants();
+  }
+
+  // Before linearization, since `(== x x)` becomes `(== x_1 x_2)` once `x` is duplicated.
+  if opts.tautological_comparisons {
+    ctx.book.fold_tautological_comparisons();
+  }
+
   ctx.book.make_var_names_unique();
 
   // Auto match linearization
@@ -156,15 +337,46 @@ pub fn desugar_book(
 }
 
 pub fn run_book(
-  mut book: Book,
+  book: Book,
   run_opts: RunOpts,
   compile_opts: CompileOpts,
   diagnostics_cfg: DiagnosticsConfig,
   args: Option<Vec<Term>>,
   cmd: &str,
 ) -> Result<Option<(Term, String, Diagnostics)>, Diagnostics> {
-  let CompileResult { hvm_book: core_book, labels, diagnostics } =
-    compile_book(&mut book, compile_opts.clone(), diagnostics_cfg, args)?;
+  let adt_encoding = compile_opts.adt_encoding;
+  let RunNetResult { net, stats, book, labels } =
+    run_book_net(book, compile_opts, diagnostics_cfg, args, cmd)?;
+  let (mut term, diags) = readback_hvm_net(&net, &book, &labels, run_opts.linear_readback, adt_encoding);
+  if run_opts.readback_depth == ReadbackDepth::HideBelowHead {
+    term.hide_below_head();
+  }
+
+  Ok(Some((term, stats, diags)))
+}
+
+/// The result of [`run_book_net`], with what's needed to read it back.
+pub struct RunNetResult {
+  /// The normalized net of the entrypoint.
+  pub net: ::hvm::ast::Net,
+  pub stats: String,
+  /// The book after it was compiled, which the readback of the net refers to.
+  pub book: Book,
+  pub labels: Labels,
+}
+
+/// Runs a book like [`run_book`], but returns the resulting net instead of reading it back as a term.
+///
+/// Useful for reading back a large result in pieces, like with [`fun::list_readback::ListReadback`].
+pub fn run_book_net(
+  mut book: Book,
+  compile_opts: CompileOpts,
+  diagnostics_cfg: DiagnosticsConfig,
+  args: Option<Vec<Term>>,
+  cmd: &str,
+) -> Result<RunNetResult, Diagnostics> {
+  let CompileResult { hvm_book: core_book, labels, diagnostics, .. } =
+    compile_book(&mut book, compile_opts, diagnostics_cfg, args)?;
 
   // TODO: Printing should be taken care by the cli module, but we'd
   // like to print any warnings before running so that the user can
@@ -173,10 +385,63 @@ pub fn run_book(
 
   let out = run_hvm(&core_book, cmd)?;
   let (net, stats) = parse_hvm_output(&out)?;
-  let (term, diags) =
-    readback_hvm_net(&net, &book, &labels, run_opts.linear_readback, compile_opts.adt_encoding);
+  Ok(RunNetResult { net, stats, book, labels })
+}
 
-  Ok(Some((term, stats, diags)))
+/// Runs each of the given definitions as the entrypoint of the program,
+/// returning their results and stats in the same order.
+///
+/// Useful for checking the values of library functions without writing a `main` for each of them.
+/// The same arguments, if any, are given to every definition.
+/// A `main` definition is left out while evaluating the others, which fails if another definition uses it.
+pub fn eval_defs(
+  book: Book,
+  defs: &[Name],
+  run_opts: RunOpts,
+  compile_opts: CompileOpts,
+  diagnostics_cfg: DiagnosticsConfig,
+  args: Option<Vec<Term>>,
+  cmd: &str,
+) -> Result<Vec<(Name, Term, String, Diagnostics)>, Diagnostics> {
+  if let Some(nam) = defs.iter().find(|nam| !book.defs.contains_key(*nam)) {
+    return Err(format!("Definition '{nam}' not found.").into());
+  }
+
+  let mut results = vec![];
+  for nam in defs {
+    let book = eval_def_book(&book, nam)?;
+    let res = run_book(book, run_opts, compile_opts.clone(), diagnostics_cfg, args.clone(), cmd)?;
+    if let Some((term, stats, diags)) = res {
+      results.push((nam.clone(), term, stats, diags));
+    }
+  }
+  Ok(results)
+}
+
+/// Returns the book that [`eval_defs`] runs to evaluate `nam`, with it as the entrypoint.
+///
+/// A custom entrypoint can't be used together with the default ones,
+/// so those are removed, as long as no other definition refers to them.
+pub fn eval_def_book(book: &Book, nam: &Name) -> Result<Book, Diagnostics> {
+  if !book.defs.contains_key(nam) {
+    return Err(format!("Definition '{nam}' not found.").into());
+  }
+
+  let mut book = book.clone();
+  for default in [ENTRY_POINT, HVM1_ENTRY_POINT].map(Name::new) {
+    if *nam == default || !book.defs.contains_key(&default) {
+      continue;
+    }
+    let used = book.defs.values().any(|def| {
+      def.name != default && def.rules.iter().any(|rule| rule.body.free_vars().contains_key(&default))
+    });
+    if used {
+      return Err(format!("Can't evaluate '{nam}', since '{default}' is used by other definitions.").into());
+    }
+    book.defs.swap_remove(&default);
+  }
+  book.entrypoint = Some(nam.clone());
+  Ok(book)
 }
 
 pub fn readback_hvm_net(
@@ -195,6 +460,18 @@ pub fn readback_hvm_net(
   (term, diags)
 }
 
+/// Reads back a result net like [`readback_hvm_net`] and converts it to JSON with [`Term::to_json`].
+pub fn readback_to_json(
+  net: &::hvm::ast::Net,
+  book: &Book,
+  labels: &Labels,
+  linear: bool,
+  adt_encoding: AdtEncoding,
+) -> (serde_json::Value, Diagnostics) {
+  let (term, diags) = readback_hvm_net(net, book, labels, linear, adt_encoding);
+  (term.to_json(book, adt_encoding), diags)
+}
+
 /// Runs an HVM book by invoking HVM as a subprocess.
 fn run_hvm(book: &::hvm::ast::Book, cmd: &str) -> Result<String, String> {
   fn filter_hvm_output(
@@ -279,6 +556,20 @@ fn parse_hvm_output(out: &str) -> Result<(::hvm::ast::Net, String), String> {
 pub struct RunOpts {
   pub linear_readback: bool,
   pub pretty: bool,
+  pub readback_depth: ReadbackDepth,
+}
+
+/// How much of the result of a run is shown.
+///
+/// This doesn't change how far the program is reduced, since HVM always normalizes the whole net.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReadbackDepth {
+  /// Only the head of the result, its weak head normal form, hiding what's under it with `*`.
+  /// The hidden parts are still computed, so this doesn't make the run any cheaper.
+  HideBelowHead,
+  /// The full normal form of the result.
+  #[default]
+  Full,
 }
 
 #[derive(Clone, Copy, Debug, Default)]
@@ -316,12 +607,33 @@ pub struct CompileOpts {
   /// Enables [fun::transform::definition_merge]
   pub merge: bool,
 
+  /// Enables [fun::transform::if_chains].
+  pub if_chains: bool,
+
+  /// Enables [fun::transform::annihilate_dup_sups].
+  pub annihilate_dup_sups: bool,
+
+  /// Enables [fun::transform::tail_recursion].
+  pub tail_recursion: bool,
+
+  /// Enables [fun::transform::inline_constants].
+  pub inline_constants: bool,
+
+  /// Enables [fun::transform::tautological_comparisons].
+  pub tautological_comparisons: bool,
+
+  /// Enables [fun::transform::comparisons_to_bool].
+  pub comparisons_to_bool: bool,
+
   /// Enables [hvmc::transform::inline].
   pub inline: bool,
 
   /// Enables [hvm::check_net_size].
   pub check_net_size: bool,
 
+  /// Records the definitions that each net was generated from, in [CompileResult::debug_symbols].
+  pub debug_symbols: bool,
+
   /// Determines the encoding of constructors and matches.
   pub adt_encoding: AdtEncoding,
 }
@@ -335,9 +647,16 @@ impl CompileOpts {
       prune: true,
       float_combinators: true,
       merge: true,
+      if_chains: self.if_chains,
+      annihilate_dup_sups: true,
+      tail_recursion: true,
+      inline_constants: true,
+      tautological_comparisons: true,
+      comparisons_to_bool: self.comparisons_to_bool,
       inline: true,
       linearize_matches: OptLevel::Enabled,
       check_net_size: self.check_net_size,
+      debug_symbols: self.debug_symbols,
       adt_encoding: self.adt_encoding,
     }
   }
@@ -351,8 +670,15 @@ impl CompileOpts {
       linearize_matches: OptLevel::Disabled,
       float_combinators: false,
       merge: false,
+      if_chains: false,
+      annihilate_dup_sups: false,
+      tail_recursion: false,
+      inline_constants: false,
+      tautological_comparisons: false,
+      comparisons_to_bool: self.comparisons_to_bool,
       inline: false,
       check_net_size: self.check_net_size,
+      debug_symbols: self.debug_symbols,
       adt_encoding: self.adt_encoding,
     }
   }
@@ -381,8 +707,15 @@ impl Default for CompileOpts {
       linearize_matches: OptLevel::Enabled,
       float_combinators: true,
       merge: false,
+      if_chains: false,
+      annihilate_dup_sups: false,
+      tail_recursion: false,
+      inline_constants: false,
+      tautological_comparisons: false,
+      comparisons_to_bool: false,
       inline: false,
       check_net_size: false,
+      debug_symbols: false,
       adt_encoding: AdtEncoding::NumScott,
     }
   }
@@ -407,6 +740,16 @@ pub struct CompileResult {
   pub diagnostics: Diagnostics,
   pub hvm_book: ::hvm::ast::Book,
   pub labels: Labels,
+  /// The origin of each net, if compiled with [CompileOpts::debug_symbols].
+  pub debug_symbols: Option<DebugSymbols>,
+}
+
+pub struct CachedCompileResult {
+  pub diagnostics: Diagnostics,
+  pub hvm_book: ::hvm::ast::Book,
+  pub labels: Labels,
+  /// Whether the book was loaded from the cache instead of being compiled.
+  pub cache_hit: bool,
 }
 
 fn maybe_grow<R, F>(f: F) -> R
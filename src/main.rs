@@ -1,12 +1,14 @@
 use bend::{
   check_book, compile_book, desugar_book,
   diagnostics::{Diagnostics, DiagnosticsConfig, Severity},
-  fun::{Book, Name},
-  hvm::display_hvm_book,
-  load_file_to_book, run_book, AdtEncoding, CompileOpts, OptLevel, RunOpts,
+  eval_defs,
+  fun::{display::colorize, Book, Name},
+  hvm::{debug_symbols::display_hvm_book_with_symbols, display_hvm_book},
+  load_file_to_book, run_book, AdtEncoding, CompileOpts, OptLevel, ReadbackDepth, RunOpts,
 };
 use clap::{Args, CommandFactory, Parser, Subcommand};
 use std::{
+  io::IsTerminal,
   path::{Path, PathBuf},
   process::ExitCode,
 };
@@ -22,6 +24,17 @@ struct Cli {
 
   #[arg(short = 'e', long, global = true, help = "Use other entrypoint rather than main or Main")]
   pub entrypoint: Option<String>,
+
+  #[arg(long, global = true, value_enum, default_value_t = ColorArg::Auto, help = "When to color the printed programs and results")]
+  pub color: ColorArg,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum ColorArg {
+  /// Colors the output when printing to a terminal.
+  Auto,
+  Always,
+  Never,
 }
 
 #[derive(Subcommand, Clone, Debug)]
@@ -45,6 +58,8 @@ enum Mode {
   },
   /// Compiles the program and runs it with the Rust HVM implementation.
   Run(RunArgs),
+  /// Runs each of the given definitions as the entrypoint and prints their results.
+  Eval(EvalArgs),
   /// Compiles the program and runs it with the C HVM implementation.
   RunC(RunArgs),
   /// Compiles the program and runs it with the Cuda HVM implementation.
@@ -104,6 +119,20 @@ struct RunArgs {
   arguments: Option<Vec<bend::fun::Term>>,
 }
 
+#[derive(Args, Clone, Debug)]
+struct EvalArgs {
+  #[arg(
+    long,
+    value_delimiter = ',',
+    required = true,
+    help = "Comma separated names of the definitions to evaluate"
+  )]
+  defs: Vec<String>,
+
+  #[command(flatten)]
+  run_args: RunArgs,
+}
+
 #[derive(Args, Clone, Debug)]
 struct GenArgs {
   #[arg(
@@ -129,6 +158,12 @@ struct CliRunOpts {
 
   #[arg(short = 's', long = "stats", help = "Shows runtime stats and rewrite counts")]
   print_stats: bool,
+
+  #[arg(
+    long = "hide-below-head",
+    help = "Hides everything under the head of the result with '*'. The program is still fully reduced"
+  )]
+  hide_below_head: bool,
 }
 
 #[derive(Args, Debug, Clone)]
@@ -177,10 +212,24 @@ pub enum OptArgs {
   NoFloatCombinators,
   Merge,
   NoMerge,
+  IfChains,
+  NoIfChains,
+  AnnihilateDupSups,
+  NoAnnihilateDupSups,
+  TailRecursion,
+  NoTailRecursion,
+  InlineConstants,
+  NoInlineConstants,
+  TautologicalComparisons,
+  NoTautologicalComparisons,
+  ComparisonsToBool,
+  NoComparisonsToBool,
   Inline,
   NoInline,
   CheckNetSize,
   NoCheckNetSize,
+  DebugSymbols,
+  NoDebugSymbols,
   AdtScott,
   AdtNumScott,
 }
@@ -201,10 +250,24 @@ fn compile_opts_from_cli(args: &Vec<OptArgs>) -> CompileOpts {
       NoFloatCombinators => opts.float_combinators = false,
       Merge => opts.merge = true,
       NoMerge => opts.merge = false,
+      IfChains => opts.if_chains = true,
+      NoIfChains => opts.if_chains = false,
+      AnnihilateDupSups => opts.annihilate_dup_sups = true,
+      NoAnnihilateDupSups => opts.annihilate_dup_sups = false,
+      TailRecursion => opts.tail_recursion = true,
+      NoTailRecursion => opts.tail_recursion = false,
+      InlineConstants => opts.inline_constants = true,
+      NoInlineConstants => opts.inline_constants = false,
+      TautologicalComparisons => opts.tautological_comparisons = true,
+      NoTautologicalComparisons => opts.tautological_comparisons = false,
+      ComparisonsToBool => opts.comparisons_to_bool = true,
+      NoComparisonsToBool => opts.comparisons_to_bool = false,
       Inline => opts.inline = true,
       NoInline => opts.inline = false,
       CheckNetSize => opts.check_net_size = true,
       NoCheckNetSize => opts.check_net_size = false,
+      DebugSymbols => opts.debug_symbols = true,
+      NoDebugSymbols => opts.debug_symbols = false,
 
       LinearizeMatches => opts.linearize_matches = OptLevel::Enabled,
       LinearizeMatchesAlt => opts.linearize_matches = OptLevel::Alt,
@@ -227,6 +290,9 @@ pub enum WarningArgs {
   UnusedDefinition,
   RepeatedBind,
   RecursionCycle,
+  UnconstructedCtr,
+  SelfApplication,
+  UnsignedUnderflow,
 }
 
 fn main() -> ExitCode {
@@ -245,6 +311,12 @@ fn main() -> ExitCode {
 fn execute_cli_mode(mut cli: Cli) -> Result<(), Diagnostics> {
   let arg_verbose = cli.verbose;
   let entrypoint = cli.entrypoint.take();
+  let color = match cli.color {
+    ColorArg::Auto => std::io::stdout().is_terminal(),
+    ColorArg::Always => true,
+    ColorArg::Never => false,
+  };
+  let paint = |code: String| if color { colorize(&code) } else { code };
 
   let load_book = |path: &Path| -> Result<Book, Diagnostics> {
     let mut book = load_file_to_book(path)?;
@@ -287,7 +359,11 @@ fn execute_cli_mode(mut cli: Cli) -> Result<(), Diagnostics> {
       let compile_res = compile_book(&mut book, opts, diagnostics_cfg, None)?;
 
       eprint!("{}", compile_res.diagnostics);
-      println!("{}", display_hvm_book(&compile_res.hvm_book));
+      if let Some(symbols) = &compile_res.debug_symbols {
+        println!("{}", display_hvm_book_with_symbols(&compile_res.hvm_book, symbols));
+      } else {
+        println!("{}", display_hvm_book(&compile_res.hvm_book));
+      }
     }
 
     Mode::GenC(GenArgs { comp_opts, warn_opts, path })
@@ -327,16 +403,16 @@ fn execute_cli_mode(mut cli: Cli) -> Result<(), Diagnostics> {
 
       eprint!("{diagnostics}");
       if pretty {
-        println!("{}", book.display_pretty())
+        println!("{}", paint(book.display_pretty().to_string()))
       } else {
-        println!("{book}");
+        println!("{}", paint(book.to_string()));
       }
     }
 
     Mode::Run(RunArgs { pretty, run_opts, comp_opts, warn_opts, path, arguments })
     | Mode::RunC(RunArgs { pretty, run_opts, comp_opts, warn_opts, path, arguments })
     | Mode::RunCu(RunArgs { pretty, run_opts, comp_opts, warn_opts, path, arguments }) => {
-      let CliRunOpts { linear, print_stats } = run_opts;
+      let CliRunOpts { linear, print_stats, hide_below_head } = run_opts;
 
       let diagnostics_cfg =
         set_warning_cfg_from_cli(DiagnosticsConfig::new(Severity::Allow, arg_verbose), warn_opts);
@@ -345,7 +421,8 @@ fn execute_cli_mode(mut cli: Cli) -> Result<(), Diagnostics> {
 
       compile_opts.check_for_strict();
 
-      let run_opts = RunOpts { linear_readback: linear, pretty };
+      let readback_depth = if hide_below_head { ReadbackDepth::HideBelowHead } else { ReadbackDepth::Full };
+      let run_opts = RunOpts { linear_readback: linear, pretty, readback_depth };
 
       let book = load_book(&path)?;
       if let Some((term, stats, diags)) =
@@ -353,9 +430,39 @@ fn execute_cli_mode(mut cli: Cli) -> Result<(), Diagnostics> {
       {
         eprint!("{diags}");
         if pretty {
-          println!("Result:\n{}", term.display_pretty(0));
+          println!("Result:\n{}", paint(term.display_pretty(0).to_string()));
+        } else {
+          println!("Result: {}", paint(term.to_string()));
+        }
+        if print_stats {
+          println!("{stats}");
+        }
+      }
+    }
+
+    Mode::Eval(EvalArgs { defs, run_args }) => {
+      let RunArgs { pretty, run_opts, comp_opts, warn_opts, path, arguments } = run_args;
+      let CliRunOpts { linear, print_stats, hide_below_head } = run_opts;
+
+      let diagnostics_cfg =
+        set_warning_cfg_from_cli(DiagnosticsConfig::new(Severity::Allow, arg_verbose), warn_opts);
+
+      let compile_opts = compile_opts_from_cli(&comp_opts);
+
+      compile_opts.check_for_strict();
+
+      let readback_depth = if hide_below_head { ReadbackDepth::HideBelowHead } else { ReadbackDepth::Full };
+      let run_opts = RunOpts { linear_readback: linear, pretty, readback_depth };
+
+      let book = load_book(&path)?;
+      let defs = defs.into_iter().map(Name::new).collect::<Vec<_>>();
+      let results = eval_defs(book, &defs, run_opts, compile_opts, diagnostics_cfg, arguments, "run")?;
+      for (nam, term, stats, diags) in results {
+        eprint!("{diags}");
+        if pretty {
+          println!("{nam}:\n{}", paint(term.display_pretty(0).to_string()));
         } else {
-          println!("Result: {}", term);
+          println!("{nam}: {}", paint(term.to_string()));
         }
         if print_stats {
           println!("{stats}");
@@ -376,6 +483,9 @@ fn set_warning_cfg_from_cli(mut cfg: DiagnosticsConfig, warn_opts: CliWarnOpts)
         cfg.unused_definition = severity;
         cfg.repeated_bind = severity;
         cfg.recursion_cycle = severity;
+        cfg.unconstructed_ctr = severity;
+        cfg.self_application = severity;
+        cfg.unsigned_underflow = severity;
       }
       WarningArgs::IrrefutableMatch => cfg.irrefutable_match = severity,
       WarningArgs::RedundantMatch => cfg.redundant_match = severity,
@@ -383,6 +493,9 @@ fn set_warning_cfg_from_cli(mut cfg: DiagnosticsConfig, warn_opts: CliWarnOpts)
       WarningArgs::UnusedDefinition => cfg.unused_definition = severity,
       WarningArgs::RepeatedBind => cfg.repeated_bind = severity,
       WarningArgs::RecursionCycle => cfg.recursion_cycle = severity,
+      WarningArgs::UnconstructedCtr => cfg.unconstructed_ctr = severity,
+      WarningArgs::SelfApplication => cfg.self_application = severity,
+      WarningArgs::UnsignedUnderflow => cfg.unsigned_underflow = severity,
     }
   }
 
@@ -1,11 +1,16 @@
 use bend::{
   compile_book, desugar_book,
   diagnostics::{Diagnostics, DiagnosticsConfig, Severity},
-  fun::{load_book::do_parse_book, net_to_term::net_to_term, term_to_net::Labels, Book, Ctx, Name, Term},
-  hvm::display_hvm_book,
+  eval_def_book, eval_defs,
+  fun::{
+    list_readback::ListReadback, load_book::do_parse_book, net_to_term::net_to_term, precompiled::export_net,
+    term_to_net::Labels, transform::unfold_fully::InlineBudget, Book, Ctx, FanKind, Name, Pattern, Tag, Term,
+  },
+  hvm::{debug_symbols::display_hvm_book_with_symbols, display_hvm_book},
   net::hvm_to_net::hvm_to_net,
-  run_book, AdtEncoding, CompileOpts, RunOpts,
+  run_book, run_book_net, AdtEncoding, CompileOpts, RunOpts,
 };
+use hvm::ast::Tree;
 use insta::assert_snapshot;
 use itertools::Itertools;
 use std::{
@@ -98,6 +103,10 @@ pub fn run_book_simple(
 
  The test functions decide how exactly to process the test programs
  and what to save as a snapshot.
+
+ Snapshots are stored in tests/snapshots and compared with insta.
+ When a change to the compiler's output is intended, update them with
+ `INSTA_UPDATE=always cargo test` (or `cargo insta review`) and check the diff.
 */
 
 #[test]
@@ -214,6 +223,124 @@ fn readback_lnet() {
   })
 }
 
+/// Truncates the results in `main` to their weak head normal form, as shown with `ReadbackDepth::HideBelowHead`.
+#[test]
+fn readback_whnf() {
+  run_golden_test_dir(function_name!(), &|code, path| {
+    let book = do_parse_book(code, path, Book::default())?;
+    let full = &book.defs[&Name::new("main")].rule().body;
+    let mut whnf = full.clone();
+    whnf.hide_below_head();
+    Ok(format!("full: {full}\nwhnf: {whnf}"))
+  })
+}
+
+/// Colors the parsed book, checking that removing the colors gives back the plain output.
+#[test]
+fn colorize() {
+  run_golden_test_dir(function_name!(), &|code, path| {
+    let book = do_parse_book(code, path, Book::default())?;
+    let plain = book.to_string();
+    let colored = bend::fun::display::colorize(&plain);
+
+    let mut uncolored = colored.clone();
+    while let Some(start) = uncolored.find('\x1b') {
+      let end = start + uncolored[start ..].find('m').unwrap();
+      uncolored.replace_range(start ..= end, "");
+    }
+    assert_eq!(uncolored, plain);
+    Ok(colored)
+  })
+}
+
+/// Reads back the elements of a list net one at a time.
+#[test]
+fn list_readback() {
+  run_golden_test_dir(function_name!(), &|code, _| {
+    let net = hvm::ast::CoreParser::new(code).parse_net()?;
+    let book = Book::builtins();
+    let labels = Labels::default();
+    let mut result = String::new();
+    for el in ListReadback::new(&net, &book, &labels, false, AdtEncoding::NumScott) {
+      match el {
+        Ok(term) => writeln!(result, "{term}").unwrap(),
+        Err(diags) => write!(result, "{diags}").unwrap(),
+      }
+    }
+    Ok(result)
+  })
+}
+
+/// Reads back each net in the `# net:` comments of the file as JSON, using the types of the file.
+#[test]
+fn readback_to_json() {
+  run_golden_test_dir(function_name!(), &|code, path| {
+    let book = do_parse_book(code, path, Book::builtins())?;
+    let mut result = String::new();
+    for net in code.lines().filter_map(|line| line.strip_prefix("# net: ")) {
+      let net = hvm::ast::CoreParser::new(net).parse_net()?;
+      let (json, diags) =
+        bend::readback_to_json(&net, &book, &Labels::default(), false, AdtEncoding::NumScott);
+      writeln!(result, "{diags}{json}").unwrap();
+    }
+    Ok(result)
+  })
+}
+
+/// Reads back a long list without building its term.
+#[test]
+fn list_readback_long() {
+  const LEN: u32 = 100_000;
+  let con = |fst: Tree, snd: Tree| Tree::Con { fst: Box::new(fst), snd: Box::new(snd) };
+  let num = |val: u32| Tree::Num { val: hvm::ast::Numb(hvm::hvm::Numb::new_u24(val).0) };
+  let var = || Tree::Var { nam: "r".to_string() };
+  // `λr (r 0)` and `λr (r 1 i tail)`
+  let mut tree = con(con(num(0), var()), var());
+  for i in (0 .. LEN).rev() {
+    tree = con(con(num(1), con(num(i), con(tree, var()))), var());
+  }
+  let net = hvm::ast::Net { root: tree, rbag: vec![] };
+
+  let book = Book::builtins();
+  let labels = Labels::default();
+  let mut count = 0;
+  for (i, el) in ListReadback::new(&net, &book, &labels, false, AdtEncoding::NumScott).enumerate() {
+    assert_eq!(el.unwrap().to_string(), i.to_string());
+    count += 1;
+  }
+  assert_eq!(count, LEN);
+
+  // The tree is too deep to be dropped recursively, so it's taken apart one node at a time.
+  let mut trees = vec![net.root];
+  while let Some(tree) = trees.pop() {
+    if let Tree::Con { fst, snd } = tree {
+      trees.push(*fst);
+      trees.push(*snd);
+    }
+  }
+}
+
+/// Reads back the list computed by a run, one element at a time.
+#[test]
+fn list_readback_run() {
+  const LEN: usize = 1_000;
+  let _guard = RUN_MUTEX.lock().unwrap();
+  let code = format!(
+    "range n acc = switch n {{ 0: acc; _: (range n-1 (List/Cons n-1 acc)) }}\nmain = (range {LEN} [])"
+  );
+  let book = do_parse_book(&code, Path::new("list_readback_run"), Book::builtins()).unwrap();
+  let compile_opts = CompileOpts::default();
+  let res = run_book_net(book, compile_opts, DiagnosticsConfig::default(), None, "run").unwrap();
+
+  let mut count = 0;
+  for (i, el) in ListReadback::new(&res.net, &res.book, &res.labels, false, AdtEncoding::NumScott).enumerate()
+  {
+    assert_eq!(el.unwrap().to_string(), i.to_string());
+    count += 1;
+  }
+  assert_eq!(count, LEN);
+}
+
 #[test]
 fn simplify_matches() {
   run_golden_test_dir(function_name!(), &|code, path| {
@@ -302,6 +429,7 @@ fn desugar_file() {
     };
     let mut book = do_parse_book(code, path, Book::builtins())?;
     desugar_book(&mut book, compile_opts, diagnostics_cfg, None)?;
+    book.assert_well_formed();
     Ok(book.to_string())
   })
 }
@@ -463,3 +591,670 @@ fn scott_triggers_unused() {
     Ok(format!("{}{}", res.diagnostics, display_hvm_book(&res.hvm_book)))
   })
 }
+
+#[test]
+fn book_diff() {
+  run_golden_test_dir(function_name!(), &|code, path| {
+    let book = do_parse_book(code, path, Book::default())?;
+    let mut new_book = book.clone();
+    new_book.encode_adts(AdtEncoding::NumScott);
+    new_book.encode_builtins();
+    Ok(book.diff(&new_book).to_string())
+  })
+}
+
+#[test]
+fn canonicalize_ops() {
+  run_golden_test_dir(function_name!(), &|code, path| {
+    let mut book = do_parse_book(code, path, Book::default())?;
+    book.canonicalize_ops();
+    Ok(book.to_string())
+  })
+}
+
+#[test]
+fn canonicalize_apps() {
+  run_golden_test_dir(function_name!(), &|code, path| {
+    let mut book = do_parse_book(code, path, Book::default())?;
+    book.canonicalize_apps();
+    Ok(book.to_string())
+  })
+}
+
+#[test]
+fn prelude() {
+  run_golden_test_dir(function_name!(), &|code, path| {
+    let mut book = do_parse_book(code, path, Book::builtins())?.with_prelude()?;
+    let compile_opts = CompileOpts::default();
+    let diagnostics_cfg = DiagnosticsConfig::default();
+    let res = compile_book(&mut book, compile_opts, diagnostics_cfg, None)?;
+    Ok(format!("{}{}", res.diagnostics, display_hvm_book(&res.hvm_book)))
+  })
+}
+
+/// Compiles a file with and without the tail recursion pass.
+#[test]
+fn tail_recursion() {
+  run_golden_test_dir(function_name!(), &|code, path| {
+    let mut result = String::new();
+    for tail_recursion in [false, true] {
+      let mut book = do_parse_book(code, path, Book::builtins())?;
+      let compile_opts = CompileOpts { tail_recursion, ..CompileOpts::default() };
+      let diagnostics_cfg = DiagnosticsConfig::default();
+      let res = compile_book(&mut book, compile_opts, diagnostics_cfg, None)?;
+      writeln!(result, "tail_recursion: {tail_recursion}\n{}", display_hvm_book(&res.hvm_book)).unwrap();
+    }
+    Ok(result)
+  })
+}
+
+/// Desugars a file with the constant inlining pass.
+#[test]
+fn inline_constants() {
+  run_golden_test_dir(function_name!(), &|code, path| {
+    let mut book = do_parse_book(code, path, Book::builtins())?;
+    let compile_opts = CompileOpts { inline_constants: true, ..CompileOpts::default() };
+    let diagnostics_cfg = DiagnosticsConfig::new(Severity::Error, true);
+    desugar_book(&mut book, compile_opts, diagnostics_cfg, None)?;
+    Ok(book.to_string())
+  })
+}
+
+#[test]
+fn nats_to_nums() {
+  run_golden_test_dir(function_name!(), &|code, path| {
+    let mut book = do_parse_book(code, path, Book::builtins())?;
+    book.encode_adts(AdtEncoding::NumScott);
+    let mut ctx = Ctx::new(&mut book, DiagnosticsConfig::default());
+    ctx.resolve_refs()?;
+    book.nats_to_nums();
+    Ok(book.defs.values().filter(|def| !def.builtin).map(|def| def.to_string()).join("\n"))
+  })
+}
+
+/// Hoists the repeated constants with at least 4 nodes.
+#[test]
+fn hoist_constants() {
+  run_golden_test_dir(function_name!(), &|code, path| {
+    let mut book = do_parse_book(code, path, Book::builtins())?;
+    book.encode_adts(AdtEncoding::NumScott);
+    book.encode_builtins();
+    let mut ctx = Ctx::new(&mut book, DiagnosticsConfig::default());
+    ctx.resolve_refs()?;
+    book.hoist_constants(4);
+    Ok(book.defs.values().filter(|def| !def.builtin).map(|def| def.to_string()).join("\n"))
+  })
+}
+
+#[test]
+fn entry_signature() {
+  run_golden_test_dir(function_name!(), &|code, path| {
+    let book = do_parse_book(code, path, Book::builtins())?;
+    let mut result = String::new();
+    for def in book.defs.values().filter(|def| !def.builtin) {
+      let sig = book.entry_signature(&def.name).unwrap();
+      writeln!(result, "{}/{}: {:?}", def.name, sig.arity(), sig.args).unwrap();
+    }
+    Ok(result)
+  })
+}
+
+#[test]
+fn tautological_comparisons() {
+  run_golden_test_dir(function_name!(), &|code, path| {
+    let mut book = do_parse_book(code, path, Book::builtins())?;
+    let compile_opts = CompileOpts { tautological_comparisons: true, ..CompileOpts::default() };
+    let diagnostics_cfg = DiagnosticsConfig {
+      unused_definition: Severity::Allow,
+      ..DiagnosticsConfig::new(Severity::Error, true)
+    };
+    desugar_book(&mut book, compile_opts, diagnostics_cfg, None)?;
+    Ok(book.defs.values().filter(|def| !def.builtin).map(|def| def.to_string()).join("\n"))
+  })
+}
+
+/// Parses a file using indentation-based layout blocks.
+///
+/// If the file has a version of the program with explicit braces after a `# braces` line,
+/// checks that both are parsed into the same book.
+#[test]
+fn layout() {
+  run_golden_test_dir(function_name!(), &|code, path| {
+    let (layout, braces) = code.split_once("# braces\n").unwrap_or((code, ""));
+    let book = do_parse_book(layout, path, Book::default())?;
+    if !braces.is_empty() {
+      let braces_book = do_parse_book(braces, path, Book::default())?;
+      assert_eq!(book.to_string(), braces_book.to_string());
+    }
+    Ok(book.to_string())
+  })
+}
+
+/// Lists the names bound in the body of each definition.
+#[test]
+fn all_binders() {
+  run_golden_test_dir(function_name!(), &|code, path| {
+    let mut book = do_parse_book(code, path, Book::builtins())?;
+    let mut ctx = Ctx::new(&mut book, DiagnosticsConfig::default());
+    ctx.fix_match_terms()?;
+    let mut result = String::new();
+    for def in book.defs.values().filter(|def| !def.builtin) {
+      let binders = def.rule().body.all_binders().into_iter().sorted().join(" ");
+      writeln!(result, "{}: {binders}", def.name).unwrap();
+    }
+    Ok(result)
+  })
+}
+
+/// Desugars a file with the pass that converts comparison results to `Bool`.
+#[test]
+fn comparisons_to_bool() {
+  run_golden_test_dir(function_name!(), &|code, path| {
+    let mut book = do_parse_book(code, path, Book::builtins())?;
+    let compile_opts = CompileOpts { comparisons_to_bool: true, ..CompileOpts::default() };
+    let diagnostics_cfg = DiagnosticsConfig::new(Severity::Error, true);
+    desugar_book(&mut book, compile_opts, diagnostics_cfg, None)?;
+    Ok(book.to_string())
+  })
+}
+
+/// Exports the compiled net of `id` and imports it back in place of the original definition.
+#[test]
+fn import_net() {
+  run_golden_test_dir(function_name!(), &|code, path| {
+    let mut book = do_parse_book(code, path, Book::builtins())?;
+    let res = compile_book(&mut book.clone(), CompileOpts::default(), DiagnosticsConfig::default(), None)?;
+
+    let id = Name::new("id");
+    let net = export_net(&res.hvm_book, &id).unwrap();
+    book.defs.shift_remove(&id);
+    book.import_net(id, net)?;
+    let imported_res = compile_book(&mut book, CompileOpts::default(), DiagnosticsConfig::default(), None)?;
+
+    let compiled = display_hvm_book(&res.hvm_book).to_string();
+    let imported = display_hvm_book(&imported_res.hvm_book).to_string();
+    assert_eq!(compiled, imported);
+    Ok(imported)
+  })
+}
+
+#[test]
+fn defs_referencing() {
+  run_golden_test_dir(function_name!(), &|code, path| {
+    let mut book = do_parse_book(code, path, Book::default())?;
+    let mut ctx = Ctx::new(&mut book, DiagnosticsConfig::default());
+    ctx.resolve_refs()?;
+
+    let mut result = String::new();
+    for name in book.defs.keys() {
+      writeln!(result, "{name}: [{}]", book.defs_referencing(name).iter().join(", ")).unwrap();
+    }
+    Ok(result)
+  })
+}
+
+/// Compiles each file with a cache, changing the source or the diagnostics options for the last compilations.
+#[test]
+fn compile_file_cached() {
+  run_golden_test_dir(function_name!(), &|code, path| {
+    let dir = std::env::temp_dir()
+      .join(format!("bend_compile_file_cached_{}", path.file_stem().unwrap().to_string_lossy()));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let compile = |code: &str, diagnostics_cfg| {
+      bend::compile_file_cached(code, path, CompileOpts::default(), diagnostics_cfg, &dir)
+    };
+    let show = |hit: bool| if hit { "hit" } else { "miss" };
+
+    let first = compile(code, DiagnosticsConfig::default())?;
+    let second = compile(code, DiagnosticsConfig::default())?;
+    assert_eq!(display_hvm_book(&first.hvm_book).to_string(), display_hvm_book(&second.hvm_book).to_string());
+    for (fst, snd) in [(&first.labels.con, &second.labels.con), (&first.labels.dup, &second.labels.dup)] {
+      assert_eq!((fst.next, &fst.label_to_name), (snd.next, &snd.label_to_name));
+    }
+    let changed = compile(&format!("{code}\n"), DiagnosticsConfig::default())?;
+    let strict = compile(code, DiagnosticsConfig::new(Severity::Error, false))?;
+
+    let mut result = String::new();
+    writeln!(result, "first: {}", show(first.cache_hit)).unwrap();
+    writeln!(result, "second: {}", show(second.cache_hit)).unwrap();
+    writeln!(result, "changed: {}", show(changed.cache_hit)).unwrap();
+    writeln!(result, "other diagnostics: {}", show(strict.cache_hit)).unwrap();
+    write!(result, "{}", display_hvm_book(&second.hvm_book)).unwrap();
+
+    let _ = std::fs::remove_dir_all(&dir);
+    Ok(result)
+  })
+}
+
+/// Fully unfolds the definitions used by `main` and shows the resulting `main`.
+#[test]
+fn unfold_fully() {
+  run_golden_test_dir(function_name!(), &|code, path| {
+    let mut book = do_parse_book(code, path, Book::default())?;
+    let mut ctx = Ctx::new(&mut book, DiagnosticsConfig::default());
+    ctx.fix_match_defs()?;
+    ctx.resolve_refs()?;
+    ctx.desugar_match_defs()?;
+
+    let main = Name::new("main");
+    for nam in book.defs[&main].rule().body.collect_refs() {
+      book.unfold_fully(&nam)?;
+    }
+    Ok(book.defs[&main].to_string())
+  })
+}
+
+/// Unfolds the definitions used by `main` with each budget set in the `# budget:` comments of the file
+/// and shows how many references were replaced and the resulting `main`.
+#[test]
+fn unfold_with_budget() {
+  run_golden_test_dir(function_name!(), &|code, path| {
+    let mut book = do_parse_book(code, path, Book::default())?;
+    let mut ctx = Ctx::new(&mut book, DiagnosticsConfig::default());
+    ctx.fix_match_defs()?;
+    ctx.resolve_refs()?;
+    ctx.desugar_match_defs()?;
+
+    let main = Name::new("main");
+    let mut result = String::new();
+    for line in code.lines().filter_map(|line| line.strip_prefix("# budget: ")) {
+      let (depth, nodes) = line.split_once(' ').unwrap();
+      let parse_limit = |limit: &str| limit.parse().unwrap_or(usize::MAX);
+      let budget = InlineBudget { max_inline_depth: parse_limit(depth), max_total_nodes: parse_limit(nodes) };
+
+      let mut book = book.clone();
+      let mut inlines = 0;
+      for nam in book.defs[&main].rule().body.collect_refs() {
+        inlines += book.unfold_with_budget(&nam, budget)?;
+      }
+      writeln!(result, "{line}: {inlines} inlines\n{}", book.defs[&main]).unwrap();
+    }
+    Ok(result)
+  })
+}
+
+/// Inlines the pure nullary definitions referenced by `hot`.
+#[test]
+fn inline_pure_nullary_refs_in() {
+  run_golden_test_dir(function_name!(), &|code, path| {
+    let mut book = do_parse_book(code, path, Book::default())?;
+    let mut ctx = Ctx::new(&mut book, DiagnosticsConfig::default());
+    ctx.fix_match_defs()?;
+    ctx.resolve_refs()?;
+    ctx.desugar_match_defs()?;
+
+    book.inline_pure_nullary_refs_in(&Name::new("hot"))?;
+    Ok(book.to_string())
+  })
+}
+
+/// Lists the symbol table of a book, before and after desugaring its match definitions.
+#[test]
+fn symbol_table() {
+  run_golden_test_dir(function_name!(), &|code, path| {
+    let mut book = do_parse_book(code, path, Book::default())?;
+    let write_table = |book: &Book, result: &mut String| {
+      for (nam, id, arity) in book.symbol_table() {
+        writeln!(result, "{nam} {id} {arity}").unwrap();
+      }
+    };
+    let mut result = String::new();
+    write_table(&book, &mut result);
+
+    let mut ctx = Ctx::new(&mut book, DiagnosticsConfig::default());
+    ctx.fix_match_defs()?;
+    ctx.resolve_refs()?;
+    ctx.desugar_match_defs()?;
+    result.push('\n');
+    write_table(&book, &mut result);
+    Ok(result)
+  })
+}
+
+/// Checks that a desugared book is well formed, and that deliberately corrupting it is detected.
+#[test]
+fn well_formed() {
+  run_golden_test_dir(function_name!(), &|code, path| {
+    let mut book = do_parse_book(code, path, Book::builtins())?;
+    desugar_book(&mut book, CompileOpts::default(), DiagnosticsConfig::default(), None)?;
+    book.assert_well_formed();
+
+    let mut result = String::new();
+    let mut check_corrupted = |desc: &str, corrupt: fn(&mut Book)| {
+      let mut book = book.clone();
+      corrupt(&mut book);
+      assert!(std::panic::catch_unwind(|| book.assert_well_formed()).is_err());
+      writeln!(result, "{desc}:\n{}", book.check_well_formed().unwrap_err()).unwrap();
+    };
+    check_corrupted("renamed definition", |book| book.defs[&Name::new("main")].name = Name::new("not_main"));
+    check_corrupted("dangling reference", |book| {
+      book.defs[&Name::new("main")].rule_mut().body = Term::r#ref("missing")
+    });
+    check_corrupted("mixed arities", |book| {
+      let mut rule = book.defs[&Name::new("main")].rule().clone();
+      rule.pats.push(Pattern::Var(None));
+      book.defs[&Name::new("main")].rules.push(rule);
+    });
+    Ok(result)
+  })
+}
+
+/// Lists the constructors used in patterns, comparing them with the declared ones.
+#[test]
+fn pattern_ctrs() {
+  run_golden_test_dir(function_name!(), &|code, path| {
+    let mut book = do_parse_book(code, path, Book::default())?;
+    let mut ctx = Ctx::new(&mut book, DiagnosticsConfig::default());
+    // Undeclared constructors are reported by `fix_match_defs`.
+    let fix_res = ctx.fix_match_defs();
+
+    let pattern_ctrs = book.pattern_ctrs();
+    let mut unmatched = book.ctrs.keys().filter(|ctr| !pattern_ctrs.contains(*ctr));
+    let mut result = String::new();
+    writeln!(result, "matched: [{}]", pattern_ctrs.iter().join(", ")).unwrap();
+    writeln!(result, "undeclared: [{}]", book.undeclared_pattern_ctrs().iter().join(", ")).unwrap();
+    writeln!(result, "unmatched: [{}]", unmatched.join(", ")).unwrap();
+    if let Err(diags) = fix_res {
+      write!(result, "{diags}").unwrap();
+    }
+    Ok(result)
+  })
+}
+
+/// Classifies the rules of each definition as trivial or not.
+#[test]
+fn rule_is_trivial() {
+  run_golden_test_dir(function_name!(), &|code, path| {
+    let book = do_parse_book(code, path, Book::default())?;
+    let mut result = String::new();
+    for def in book.defs.values() {
+      for rule in def.rules.iter() {
+        let class = if rule.is_trivial() { "trivial" } else { "not trivial" };
+        writeln!(result, "{}: {class}", rule.display(&def.name)).unwrap();
+      }
+    }
+    Ok(result)
+  })
+}
+
+/// Writes each compiled definition to its own file and reads them back.
+#[test]
+fn compile_to_files() {
+  run_golden_test_dir(function_name!(), &|code, path| {
+    let dir = std::env::temp_dir()
+      .join(format!("bend_compile_to_files_{}", path.file_stem().unwrap().to_string_lossy()));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let mut book = do_parse_book(code, path, Book::builtins())?;
+    bend::compile_to_files(&mut book, CompileOpts::default(), DiagnosticsConfig::default(), &dir)?;
+
+    let mut result = String::new();
+    let files = std::fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().path()).sorted().collect_vec();
+    for file in files {
+      let contents = std::fs::read_to_string(&file).unwrap();
+      writeln!(result, "{}:\n{contents}", file.file_name().unwrap().to_string_lossy()).unwrap();
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+    Ok(result)
+  })
+}
+
+#[test]
+fn numeric_literals() {
+  run_golden_test_dir(function_name!(), &|code, path| {
+    let book = do_parse_book(code, path, Book::default())?;
+    let (u24s, i24s) = book.numeric_literals();
+    Ok(format!("u24: {u24s:?}\ni24: {i24s:?}"))
+  })
+}
+
+#[test]
+fn unconstructed_ctrs() {
+  run_golden_test_dir(function_name!(), &|code, path| {
+    let mut book = do_parse_book(code, path, Book::builtins())?;
+    let opts = CompileOpts::default();
+    let diagnostics_cfg =
+      DiagnosticsConfig { unconstructed_ctr: Severity::Warning, ..DiagnosticsConfig::default() };
+    let res = compile_book(&mut book, opts, diagnostics_cfg, None)?;
+    Ok(format!("{}{}", res.diagnostics, display_hvm_book(&res.hvm_book)))
+  })
+}
+
+#[test]
+fn self_application() {
+  run_golden_test_dir(function_name!(), &|code, path| {
+    let mut book = do_parse_book(code, path, Book::builtins())?;
+    let opts = CompileOpts::default();
+    let diagnostics_cfg =
+      DiagnosticsConfig { self_application: Severity::Warning, ..DiagnosticsConfig::default() };
+    let res = compile_book(&mut book, opts, diagnostics_cfg, None)?;
+    Ok(format!("{}{}", res.diagnostics, display_hvm_book(&res.hvm_book)))
+  })
+}
+
+#[test]
+fn unsigned_underflow() {
+  run_golden_test_dir(function_name!(), &|code, path| {
+    let mut book = do_parse_book(code, path, Book::builtins())?;
+    let diagnostics_cfg =
+      DiagnosticsConfig { unsigned_underflow: Severity::Warning, ..DiagnosticsConfig::default() };
+    let res = compile_book(&mut book, CompileOpts::default(), diagnostics_cfg, None)?;
+    Ok(format!("{}{}", res.diagnostics, display_hvm_book(&res.hvm_book)))
+  })
+}
+
+#[test]
+fn debug_symbols() {
+  run_golden_test_dir(function_name!(), &|code, path| {
+    let mut book = do_parse_book(code, path, Book::builtins())?;
+    let opts = CompileOpts { debug_symbols: true, merge: true, ..CompileOpts::default() };
+    let diagnostics_cfg = DiagnosticsConfig { unused_definition: Severity::Allow, ..Default::default() };
+    let res = compile_book(&mut book, opts, diagnostics_cfg, None)?;
+    let symbols = res.debug_symbols.unwrap();
+    Ok(format!("{}{}", res.diagnostics, display_hvm_book_with_symbols(&res.hvm_book, &symbols)))
+  })
+}
+
+/// Problems found only while lowering to nets are reported by `compile_check`, but not by `desugar_book`.
+#[test]
+fn compile_check() {
+  run_golden_test_dir(function_name!(), &|code, path| {
+    let book = do_parse_book(code, path, Book::builtins())?;
+    let opts = CompileOpts { check_net_size: true, ..CompileOpts::default() };
+    let diagnostics_cfg = DiagnosticsConfig::default();
+
+    let desugared = desugar_book(&mut book.clone(), opts.clone(), diagnostics_cfg, None);
+    let mut result = format!("desugar_book: {}\n", if desugared.is_ok() { "ok" } else { "error" });
+    for (orig, diag) in bend::compile_check(&book, opts, diagnostics_cfg) {
+      writeln!(result, "{orig:?} {:?}: {}", diag.severity(), diag.message()).unwrap();
+    }
+    Ok(result)
+  })
+}
+
+#[test]
+fn call_graph_sccs() {
+  run_golden_test_dir(function_name!(), &|code, path| {
+    let mut book = do_parse_book(code, path, Book::builtins())?;
+    let mut ctx = Ctx::new(&mut book, Default::default());
+    ctx.resolve_refs()?;
+    let sccs = book.call_graph_sccs();
+    let user_sccs = sccs.iter().filter(|scc| scc.iter().any(|nam| !book.defs[nam].builtin));
+    Ok(user_sccs.map(|scc| scc.iter().join(", ")).join("\n"))
+  })
+}
+
+#[test]
+fn if_chains() {
+  run_golden_test_dir(function_name!(), &|code, path| {
+    let compile_opts = CompileOpts { if_chains: true, ..CompileOpts::default() };
+    let diagnostics_cfg = DiagnosticsConfig {
+      unused_definition: Severity::Allow,
+      ..DiagnosticsConfig::new(Severity::Error, true)
+    };
+    let mut book = do_parse_book(code, path, Book::builtins())?;
+    desugar_book(&mut book, compile_opts, diagnostics_cfg, None)?;
+    Ok(book.to_string())
+  })
+}
+
+#[test]
+fn annihilate_dup_sups() {
+  run_golden_test_dir(function_name!(), &|code, path| {
+    let compile_opts = CompileOpts { annihilate_dup_sups: true, ..CompileOpts::default() };
+    let diagnostics_cfg = DiagnosticsConfig {
+      unused_definition: Severity::Allow,
+      ..DiagnosticsConfig::new(Severity::Error, true)
+    };
+    let mut book = do_parse_book(code, path, Book::builtins())?;
+    desugar_book(&mut book, compile_opts, diagnostics_cfg, None)?;
+    Ok(book.to_string())
+  })
+}
+
+/// Only fans with the same label annihilate, so only those dups of sups are simplified.
+///
+/// Labels can't be written in programs, so the terms are built directly.
+#[test]
+fn annihilate_dup_sups_labels() {
+  let dup_sup = |dup_tag: Tag, sup_tag: Tag| {
+    let var = |nam: &str| Term::Var { nam: Name::new(nam) };
+    let bind = |nam: &str| Pattern::Var(Some(Name::new(nam)));
+    Term::Let {
+      pat: Box::new(Pattern::Fan(FanKind::Dup, dup_tag, vec![bind("x"), bind("y")])),
+      val: Box::new(Term::Fan { fan: FanKind::Dup, tag: sup_tag, els: vec![var("a"), var("b")] }),
+      nxt: Box::new(Term::app(var("x"), var("y"))),
+    }
+  };
+
+  let mut same = dup_sup(Tag::Numeric(1), Tag::Numeric(1));
+  same.annihilate_dup_sups();
+  assert_eq!(same.to_string(), "let x = a; let y = b; (x y)");
+
+  for (dup_tag, sup_tag) in
+    [(Tag::Numeric(1), Tag::Numeric(2)), (Tag::Auto, Tag::Numeric(1)), (Tag::Static, Tag::Static)]
+  {
+    let mut different = dup_sup(dup_tag, sup_tag);
+    let original = different.to_string();
+    different.annihilate_dup_sups();
+    assert_eq!(different.to_string(), original);
+  }
+}
+
+#[test]
+fn pattern_helpers() {
+  let built = Pattern::ctr("List/Cons", [
+    Pattern::tup([Pattern::var("x"), Pattern::num(1)]),
+    Pattern::ctr("List/Cons", [Pattern::wildcard(), Pattern::var("xs")]),
+  ]);
+  assert_eq!(built.to_string(), "(List/Cons (x, 1) (List/Cons * xs))");
+  assert_eq!(built, Pattern::from("(List/Cons (x, 1) (List/Cons * xs))"));
+}
+
+#[test]
+#[should_panic(expected = "Invalid pattern")]
+fn pattern_from_invalid_str() {
+  let _ = Pattern::from("(x, 1) extra");
+}
+
+#[test]
+fn rule_continuation() {
+  let continued = "(len (Nil)) = 0\n| (Cons h t) = (+ 1 (len t))\n@priority 1\n| Zero = 0";
+  let repeated = "(len (Nil)) = 0\n(len (Cons h t)) = (+ 1 (len t))\n@priority 1\n(len Zero) = 0";
+  let continued = do_parse_book(continued, Path::new("continued"), Book::default()).unwrap();
+  let repeated = do_parse_book(repeated, Path::new("repeated"), Book::default()).unwrap();
+  assert_eq!(continued.defs[&Name::new("len")].rules.len(), 3);
+  assert_eq!(continued.to_string(), repeated.to_string());
+}
+
+#[test]
+fn renamed_channels() {
+  let parse = |code: &str, prefix: &str| {
+    let mut book = do_parse_book(code, Path::new(prefix), Book::default()).unwrap();
+    book.with_renamed_channels(prefix);
+    book
+  };
+  let a = parse("get_a = λ$c (λx x $c)", "a_");
+  let b = parse("get_b = λf ((f λ$c *), $c)", "b_");
+
+  assert_eq!(a.to_string().trim(), "(get_a) = λ$a_c (λx x $a_c)");
+  assert_eq!(b.to_string().trim(), "(get_b) = λf ((f λ$b_c *), $b_c)");
+
+  // Both channels are still bound where they're used once the books are merged.
+  let mut merged = Book::builtins();
+  merged.defs.extend(a.defs);
+  merged.defs.extend(b.defs);
+  merged.defs.insert(Name::new("main"), parse("main = (get_a, get_b)", "main_").defs.pop().unwrap().1);
+  let res = compile_book(&mut merged, CompileOpts::default(), DiagnosticsConfig::default(), None).unwrap();
+  assert_eq!(res.diagnostics.to_string(), "");
+}
+
+/// A definition called `nam`, to take a name that a pass would generate.
+fn taken_def(nam: &str) -> bend::fun::Definition {
+  let mut def =
+    do_parse_book("taken = 0", Path::new("taken"), Book::default()).unwrap().defs.pop().unwrap().1;
+  def.name = Name::new(nam);
+  def
+}
+
+#[test]
+fn hoist_constants_name_taken() {
+  let code =
+    "sum (List/Nil) = 0\nsum (List/Cons x xs) = (+ x (sum xs))\nfirst = (sum [1, 2])\nmain = (sum [1, 2])";
+  let mut book = do_parse_book(code, Path::new("hoist_constants"), Book::builtins()).unwrap();
+  book.defs.insert(Name::new("first__K0"), taken_def("first__K0"));
+  book.encode_adts(AdtEncoding::NumScott);
+  book.encode_builtins();
+  Ctx::new(&mut book, DiagnosticsConfig::default()).resolve_refs().unwrap();
+  book.hoist_constants(4);
+  assert_eq!(book.defs[&Name::new("first__K0")].to_string(), "(first__K0) = 0");
+  assert_eq!(book.defs[&Name::new("first")].to_string(), "(first) = (sum first__K1)");
+}
+
+#[test]
+fn tail_recursion_name_taken() {
+  let code = "sum n = switch n {\n  0: 0\n  _: (+ n (sum n-1))\n}\nmain = (sum 10)";
+  let mut book = do_parse_book(code, Path::new("tail_recursion"), Book::builtins()).unwrap();
+  book.defs.insert(Name::new("sum__tail"), taken_def("sum__tail"));
+  let compile_opts = CompileOpts { tail_recursion: true, ..CompileOpts::default() };
+  desugar_book(&mut book, compile_opts, DiagnosticsConfig::default(), None).unwrap();
+  assert_eq!(book.defs[&Name::new("sum__tail")].to_string(), "(sum__tail) = 0");
+  assert!(book.defs.contains_key(&Name::new("sum__tail0")));
+}
+
+#[test]
+fn eval_defs_books() {
+  let parse = |code: &str| do_parse_book(code, Path::new("eval_defs"), Book::builtins()).unwrap();
+  let names = |names: &[&str]| names.iter().map(|nam| Name::new(*nam)).collect::<Vec<_>>();
+  let user_defs = |book: &Book| book.defs.values().filter(|def| !def.builtin).map(|def| &def.name).join(", ");
+  let eval = |book: Book, defs: &[&str]| {
+    let opts = CompileOpts::default();
+    eval_defs(book, &names(defs), RunOpts::default(), opts, DiagnosticsConfig::default(), None, "run")
+  };
+
+  // Nothing is run if one of the definitions doesn't exist.
+  let book = parse("double x = (* x 2)\nmain = (double 1)");
+  let err = eval(book, &["double", "missing"]).unwrap_err();
+  assert!(err.to_string().contains("Definition 'missing' not found."), "{err}");
+
+  // Each definition gets its own book, without the unused main.
+  let book = parse("one = 1\ntwo = 2\nmain = 3");
+  for nam in ["one", "two"] {
+    let eval_book = eval_def_book(&book, &Name::new(nam)).unwrap();
+    assert_eq!(eval_book.entrypoint, Some(Name::new(nam)));
+    assert_eq!(user_defs(&eval_book), "one, two");
+  }
+  let eval_book = eval_def_book(&book, &Name::new("main")).unwrap();
+  assert_eq!(user_defs(&eval_book), "one, two, main");
+
+  // Main is also removed, but not when another definition uses it.
+  let book = parse("one = 1\nMain = 2");
+  assert_eq!(user_defs(&eval_def_book(&book, &Name::new("one")).unwrap()), "one");
+  let book = parse("one = (+ main 1)\nmain = 2");
+  let err = eval(book, &["one"]).unwrap_err();
+  assert!(
+    err.to_string().contains("Can't evaluate 'one', since 'main' is used by other definitions."),
+    "{err}"
+  );
+}